@@ -0,0 +1,112 @@
+use std::fmt;
+
+
+/// Size of the smallest cartridge, two 16 KiB ROM banks with no MBC
+pub const MIN_ROM_SIZE: usize = 0x8000;
+
+/// The logo every licensed cartridge carries at 0x0104 - 0x0133; the boot ROM refuses to run a
+/// cartridge whose logo doesn't match this
+pub const NINTENDO_LOGO: [u8; 48] = [
+    0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
+    0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
+    0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E
+];
+
+
+/// A ROM that can't be loaded at all
+#[derive(Debug, Clone, PartialEq)]
+pub enum HeaderError {
+
+    /// The ROM is smaller than the smallest (32 KiB) cartridge, so can't even hold a header
+    TooSmall { size: usize }
+}
+
+
+impl fmt::Display for HeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HeaderError::TooSmall { size } =>
+                write!(f, "ROM of {} bytes is smaller than the minimum of 32 KiB", size)
+        }
+    }
+}
+
+
+impl std::error::Error for HeaderError {}
+
+
+/// Something wrong with a header that doesn't stop the cartridge from being emulated. Real
+/// hardware would refuse to boot some of these (a bad logo or header checksum), but plenty of
+/// homebrew and patched ROMs get them wrong and run fine regardless.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HeaderWarning {
+
+    /// The Nintendo logo at 0x0104 - 0x0133 doesn't match
+    LogoMismatch,
+
+    /// The header checksum at 0x014D doesn't match the bytes 0x0134 - 0x014C
+    HeaderChecksumMismatch { expected: u8, actual: u8 },
+
+    /// The global checksum at 0x014E - 0x014F doesn't match the sum of the ROM
+    GlobalChecksumMismatch { expected: u16, actual: u16 },
+
+    /// The ROM size code at 0x0148 isn't one of the known values
+    UnknownRomSize { code: u8 },
+
+    /// The ROM is a different size than its header claims
+    RomSizeMismatch { expected: usize, actual: usize }
+}
+
+
+impl fmt::Display for HeaderWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HeaderWarning::LogoMismatch =>
+                write!(f, "Nintendo logo does not match"),
+
+            HeaderWarning::HeaderChecksumMismatch { expected, actual } =>
+                write!(f, "header checksum is {:#04X}, but the header sums to {:#04X}", actual, expected),
+
+            HeaderWarning::GlobalChecksumMismatch { expected, actual } =>
+                write!(f, "global checksum is {:#06X}, but the ROM sums to {:#06X}", actual, expected),
+
+            HeaderWarning::UnknownRomSize { code } =>
+                write!(f, "unknown ROM size code {:#04X}", code),
+
+            HeaderWarning::RomSizeMismatch { expected, actual } =>
+                write!(f, "header declares {} bytes of ROM, but the ROM is {} bytes", expected, actual)
+        }
+    }
+}
+
+
+/// The header checksum, computed over 0x0134 - 0x014C as the boot ROM does
+pub fn header_checksum(data: &[u8]) -> u8 {
+    data[0x0134..=0x014C].iter().fold(0u8, |x, byte| x.wrapping_sub(*byte).wrapping_sub(1))
+}
+
+/// The global checksum, the sum of every byte in the ROM except the checksum itself
+pub fn global_checksum(data: &[u8]) -> u16 {
+    data.iter().enumerate()
+        .filter(|(i, _)| *i != 0x014E && *i != 0x014F)
+        .fold(0u16, |sum, (_, byte)| sum.wrapping_add(*byte as u16))
+}
+
+/// Whether the title area carries a 4-character manufacturer code at 0x013F - 0x0142, limiting
+///  the title itself to 11 bytes. Only later (CGB era) cartridges have one, and then it is always
+///  made up of uppercase letters and digits.
+pub fn has_manufacturer_code(data: &[u8]) -> bool {
+    data[0x0143] & 0x80 != 0 &&
+        data[0x013F..=0x0142].iter().all(|b| b.is_ascii_uppercase() || b.is_ascii_digit())
+}
+
+/// Decode a title leniently: it ends at the first NUL, and anything that isn't printable ASCII
+///  (as many Japanese titles contain) is replaced rather than rejected
+pub fn decode_title(bytes: &[u8]) -> String {
+    bytes.iter()
+        .take_while(|b| **b != 0)
+        .map(|b| if b.is_ascii_graphic() || *b == b' ' { *b as char } else { char::REPLACEMENT_CHARACTER })
+        .collect::<String>()
+        .trim_end()
+        .to_string()
+}
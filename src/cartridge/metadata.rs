@@ -0,0 +1,490 @@
+use std::fmt;
+
+
+/// The memory bank controller (or other hardware) on a cartridge, from 0x0147
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Mapper {
+    RomOnly,
+    MBC1,
+    MBC2,
+    MMM01,
+    MBC3,
+    MBC5,
+    MBC6,
+    MBC7,
+    PocketCamera,
+    BandaiTama5,
+    HuC3,
+    HuC1,
+
+    // Unlicensed multicart mappers, never named in a header; see multicart::detect
+    M161,
+    WisdomTree,
+
+    Unknown(u8)
+}
+
+
+/// Extra hardware a cartridge carries alongside its mapper
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Features {
+    pub ram: bool,          // External RAM
+    pub battery: bool,      // Battery keeping RAM (and any timer) alive while powered off
+    pub timer: bool,        // Real-time clock
+    pub rumble: bool,       // Rumble motor
+    pub sensor: bool        // Accelerometer
+}
+
+
+/// The decoded cartridge type byte at 0x0147
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CartridgeType {
+    pub mapper: Mapper,
+    pub features: Features
+}
+
+
+impl CartridgeType {
+
+    pub fn from_code(code: u8) -> CartridgeType {
+
+        let (ram, battery, timer, rumble, sensor) = (true, true, true, true, true);
+        let none = Features::default();
+
+        let (mapper, features) = match code {
+            0x00 => (Mapper::RomOnly, none),
+            0x01 => (Mapper::MBC1, none),
+            0x02 => (Mapper::MBC1, Features { ram, ..none }),
+            0x03 => (Mapper::MBC1, Features { ram, battery, ..none }),
+            0x05 => (Mapper::MBC2, none),
+            0x06 => (Mapper::MBC2, Features { battery, ..none }),
+            0x08 => (Mapper::RomOnly, Features { ram, ..none }),
+            0x09 => (Mapper::RomOnly, Features { ram, battery, ..none }),
+            0x0B => (Mapper::MMM01, none),
+            0x0C => (Mapper::MMM01, Features { ram, ..none }),
+            0x0D => (Mapper::MMM01, Features { ram, battery, ..none }),
+            0x0F => (Mapper::MBC3, Features { timer, battery, ..none }),
+            0x10 => (Mapper::MBC3, Features { timer, ram, battery, ..none }),
+            0x11 => (Mapper::MBC3, none),
+            0x12 => (Mapper::MBC3, Features { ram, ..none }),
+            0x13 => (Mapper::MBC3, Features { ram, battery, ..none }),
+            0x19 => (Mapper::MBC5, none),
+            0x1A => (Mapper::MBC5, Features { ram, ..none }),
+            0x1B => (Mapper::MBC5, Features { ram, battery, ..none }),
+            0x1C => (Mapper::MBC5, Features { rumble, ..none }),
+            0x1D => (Mapper::MBC5, Features { rumble, ram, ..none }),
+            0x1E => (Mapper::MBC5, Features { rumble, ram, battery, ..none }),
+            0x20 => (Mapper::MBC6, none),
+            0x22 => (Mapper::MBC7, Features { sensor, rumble, ram, battery, ..none }),
+            0xFC => (Mapper::PocketCamera, Features { sensor, ram, battery, ..none }),
+            0xFD => (Mapper::BandaiTama5, none),
+            0xFE => (Mapper::HuC3, Features { timer, ram, battery, ..none }),
+            0xFF => (Mapper::HuC1, Features { ram, battery, ..none }),
+
+            _ => (Mapper::Unknown(code), none)
+        };
+
+        CartridgeType { mapper, features }
+    }
+}
+
+
+/// The ROM size declared at 0x0148, in 16 KiB banks
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RomSize {
+    pub banks: usize,
+    pub bytes: usize
+}
+
+
+impl RomSize {
+
+    pub fn from_code(code: u8) -> Option<RomSize> {
+
+        let banks = match code {
+            0x00 ..= 0x08 => 2 << code,
+
+            // Listed in some sources, though no cartridge is known to use them
+            0x52 => 72,
+            0x53 => 80,
+            0x54 => 96,
+
+            _ => return None
+        };
+
+        Some(RomSize { banks, bytes: banks * 0x4000 })
+    }
+}
+
+
+/// The external RAM size declared at 0x0149, in bytes
+pub fn ram_size_bytes(code: u8) -> Option<usize> {
+    match code {
+        0x00 => Some(0),
+        0x01 => Some(0x800),    // Unused officially, but 2 KiB on some homebrew / early carts
+        0x02 => Some(0x2000),
+        0x03 => Some(0x8000),
+        0x04 => Some(0x20000),
+        0x05 => Some(0x10000),
+
+        _ => None
+    }
+}
+
+
+/// Where a cartridge was sold, from 0x014A
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Destination {
+    Japanese,
+    Overseas,
+    Unknown(u8)
+}
+
+
+impl Destination {
+
+    pub fn from_code(code: u8) -> Destination {
+        match code {
+            0x00 => Destination::Japanese,
+            0x01 => Destination::Overseas,
+            _ => Destination::Unknown(code)
+        }
+    }
+}
+
+
+/// How a cartridge uses the Color GameBoy, from 0x0143
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CgbSupport {
+    None,           // A DMG game; runs in (colourised) compatibility mode on a CGB
+    Enhanced,       // Works on any model, with colour on a CGB
+    Only            // Requires a CGB
+}
+
+
+impl CgbSupport {
+
+    pub fn from_code(code: u8) -> CgbSupport {
+        match code & 0xC0 {
+            0xC0 => CgbSupport::Only,
+            0x80 => CgbSupport::Enhanced,
+            _ => CgbSupport::None
+        }
+    }
+}
+
+
+/// Whether a cartridge uses Super GameBoy functions; only honoured with 0x03 at 0x0146 AND the
+/// old licensee code set to 0x33
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SgbSupport {
+    None,
+    Supported
+}
+
+
+impl SgbSupport {
+
+    pub fn from_codes(sgb_flag: u8, old_licensee_code: u8) -> SgbSupport {
+        match sgb_flag == 0x03 && old_licensee_code == 0x33 {
+            true  => SgbSupport::Supported,
+            false => SgbSupport::None
+        }
+    }
+}
+
+
+/// The publisher of a cartridge. Older cartridges use a one byte code at 0x014B; newer ones set
+/// that to 0x33 and use a two character code at 0x0144 - 0x0145 instead.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Licensee {
+    Old { code: u8, name: Option<&'static str> },
+    New { code: [u8; 2], name: Option<&'static str> }
+}
+
+
+impl Licensee {
+
+    pub fn from_codes(old_licensee_code: u8, new_licensee_code: [u8; 2]) -> Licensee {
+        match old_licensee_code {
+            0x33 => Licensee::New { code: new_licensee_code, name: new_licensee_name(new_licensee_code) },
+            code => Licensee::Old { code, name: old_licensee_name(code) }
+        }
+    }
+
+    /// The publisher's name, if the code is a known one
+    pub fn name(&self) -> Option<&'static str> {
+        match self {
+            Licensee::Old { name, .. } => *name,
+            Licensee::New { name, .. } => *name
+        }
+    }
+}
+
+
+impl fmt::Display for Mapper {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Mapper::RomOnly => write!(f, "ROM Only"),
+            Mapper::PocketCamera => write!(f, "Pocket Camera"),
+            Mapper::BandaiTama5 => write!(f, "Bandai TAMA5"),
+            Mapper::WisdomTree => write!(f, "Wisdom Tree"),
+            Mapper::Unknown(code) => write!(f, "Unknown ({:#04X})", code),
+            mapper => write!(f, "{:?}", mapper)
+        }
+    }
+}
+
+
+impl fmt::Display for CartridgeType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.mapper)?;
+
+        for (present, name) in [(self.features.ram, "RAM"), (self.features.battery, "Battery"),
+                                (self.features.timer, "Timer"), (self.features.rumble, "Rumble"),
+                                (self.features.sensor, "Sensor")].iter() {
+            if *present {
+                write!(f, " +{}", name)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+
+impl fmt::Display for Licensee {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = self.name().unwrap_or("Unknown");
+        match self {
+            Licensee::Old { code, .. } => write!(f, "{} ({:#04X})", name, code),
+            Licensee::New { code, .. } => write!(f, "{} (\"{}\")", name, String::from_utf8_lossy(code))
+        }
+    }
+}
+
+
+fn old_licensee_name(code: u8) -> Option<&'static str> {
+    Some(match code {
+        0x00 => "None",
+        0x01 => "Nintendo",
+        0x08 => "Capcom",
+        0x09 => "Hot-B",
+        0x0A => "Jaleco",
+        0x0B => "Coconuts Japan",
+        0x0C => "Elite Systems",
+        0x13 => "Electronic Arts",
+        0x18 => "Hudson Soft",
+        0x19 => "ITC Entertainment",
+        0x1A => "Yanoman",
+        0x1D => "Japan Clary",
+        0x1F => "Virgin Interactive",
+        0x24 => "PCM Complete",
+        0x25 => "San-X",
+        0x28 => "Kotobuki Systems",
+        0x29 => "Seta",
+        0x30 => "Infogrames",
+        0x31 => "Nintendo",
+        0x32 => "Bandai",
+        0x34 => "Konami",
+        0x35 => "HectorSoft",
+        0x38 => "Capcom",
+        0x39 => "Banpresto",
+        0x3C => "Entertainment International",
+        0x3E => "Gremlin",
+        0x41 => "Ubisoft",
+        0x42 => "Atlus",
+        0x44 => "Malibu",
+        0x46 => "Angel",
+        0x47 => "Spectrum Holobyte",
+        0x49 => "Irem",
+        0x4A => "Virgin Interactive",
+        0x4D => "Malibu",
+        0x4F => "U.S. Gold",
+        0x50 => "Absolute",
+        0x51 => "Acclaim",
+        0x52 => "Activision",
+        0x53 => "American Sammy",
+        0x54 => "GameTek",
+        0x55 => "Park Place",
+        0x56 => "LJN",
+        0x57 => "Matchbox",
+        0x59 => "Milton Bradley",
+        0x5A => "Mindscape",
+        0x5B => "Romstar",
+        0x5C => "Naxat Soft",
+        0x5D => "Tradewest",
+        0x60 => "Titus",
+        0x61 => "Virgin Interactive",
+        0x67 => "Ocean",
+        0x69 => "Electronic Arts",
+        0x6E => "Elite Systems",
+        0x6F => "Electro Brain",
+        0x70 => "Infogrames",
+        0x71 => "Interplay",
+        0x72 => "Broderbund",
+        0x73 => "Sculptured Software",
+        0x75 => "The Sales Curve",
+        0x78 => "THQ",
+        0x79 => "Accolade",
+        0x7A => "Triffix Entertainment",
+        0x7C => "Microprose",
+        0x7F => "Kemco",
+        0x80 => "Misawa Entertainment",
+        0x83 => "LOZC",
+        0x86 => "Tokuma Shoten Intermedia",
+        0x8B => "Bullet-Proof Software",
+        0x8C => "Vic Tokai",
+        0x8E => "Ape",
+        0x8F => "I'Max",
+        0x91 => "Chunsoft",
+        0x92 => "Video System",
+        0x93 => "Tsuburaya Productions",
+        0x95 => "Varie",
+        0x96 => "Yonezawa/S'Pal",
+        0x97 => "Kaneko",
+        0x99 => "Arc",
+        0x9A => "Nihon Bussan",
+        0x9B => "Tecmo",
+        0x9C => "Imagineer",
+        0x9D => "Banpresto",
+        0x9F => "Nova",
+        0xA1 => "Hori Electric",
+        0xA2 => "Bandai",
+        0xA4 => "Konami",
+        0xA6 => "Kawada",
+        0xA7 => "Takara",
+        0xA9 => "Technos Japan",
+        0xAA => "Broderbund",
+        0xAC => "Toei Animation",
+        0xAD => "Toho",
+        0xAF => "Namco",
+        0xB0 => "Acclaim",
+        0xB1 => "ASCII / Nexsoft",
+        0xB2 => "Bandai",
+        0xB4 => "Square Enix",
+        0xB6 => "HAL Laboratory",
+        0xB7 => "SNK",
+        0xB9 => "Pony Canyon",
+        0xBA => "Culture Brain",
+        0xBB => "Sunsoft",
+        0xBD => "Sony Imagesoft",
+        0xBF => "Sammy",
+        0xC0 => "Taito",
+        0xC2 => "Kemco",
+        0xC3 => "Squaresoft",
+        0xC4 => "Tokuma Shoten Intermedia",
+        0xC5 => "Data East",
+        0xC6 => "Tonkinhouse",
+        0xC8 => "Koei",
+        0xC9 => "UFL",
+        0xCA => "Ultra",
+        0xCB => "Vap",
+        0xCC => "Use Corporation",
+        0xCD => "Meldac",
+        0xCE => "Pony Canyon",
+        0xCF => "Angel",
+        0xD0 => "Taito",
+        0xD1 => "Sofel",
+        0xD2 => "Quest",
+        0xD3 => "Sigma Enterprises",
+        0xD4 => "ASK Kodansha",
+        0xD6 => "Naxat Soft",
+        0xD7 => "Copya System",
+        0xD9 => "Banpresto",
+        0xDA => "Tomy",
+        0xDB => "LJN",
+        0xDD => "NCS",
+        0xDE => "Human",
+        0xDF => "Altron",
+        0xE0 => "Jaleco",
+        0xE1 => "Towa Chiki",
+        0xE2 => "Yutaka",
+        0xE3 => "Varie",
+        0xE5 => "Epoch",
+        0xE7 => "Athena",
+        0xE8 => "Asmik Ace Entertainment",
+        0xE9 => "Natsume",
+        0xEA => "King Records",
+        0xEB => "Atlus",
+        0xEC => "Epic / Sony Records",
+        0xEE => "IGS",
+        0xF0 => "A Wave",
+        0xF3 => "Extreme Entertainment",
+        0xFF => "LJN",
+
+        _ => return None
+    })
+}
+
+
+fn new_licensee_name(code: [u8; 2]) -> Option<&'static str> {
+    Some(match &code {
+        b"00" => "None",
+        b"01" => "Nintendo",
+        b"08" => "Capcom",
+        b"13" => "Electronic Arts",
+        b"18" => "Hudson Soft",
+        b"19" => "B-AI",
+        b"20" => "KSS",
+        b"22" => "Planning Office WADA",
+        b"24" => "PCM Complete",
+        b"25" => "San-X",
+        b"28" => "Kemco",
+        b"29" => "Seta",
+        b"30" => "Viacom",
+        b"31" => "Nintendo",
+        b"32" => "Bandai",
+        b"33" => "Ocean / Acclaim",
+        b"34" => "Konami",
+        b"35" => "HectorSoft",
+        b"37" => "Taito",
+        b"38" => "Hudson Soft",
+        b"39" => "Banpresto",
+        b"41" => "Ubisoft",
+        b"42" => "Atlus",
+        b"44" => "Malibu",
+        b"46" => "Angel",
+        b"47" => "Bullet-Proof Software",
+        b"49" => "Irem",
+        b"50" => "Absolute",
+        b"51" => "Acclaim",
+        b"52" => "Activision",
+        b"53" => "American Sammy",
+        b"54" => "Konami",
+        b"55" => "Hi Tech Entertainment",
+        b"56" => "LJN",
+        b"57" => "Matchbox",
+        b"58" => "Mattel",
+        b"59" => "Milton Bradley",
+        b"60" => "Titus",
+        b"61" => "Virgin Interactive",
+        b"64" => "LucasArts",
+        b"67" => "Ocean",
+        b"69" => "Electronic Arts",
+        b"70" => "Infogrames",
+        b"71" => "Interplay",
+        b"72" => "Broderbund",
+        b"73" => "Sculptured Software",
+        b"75" => "The Sales Curve",
+        b"78" => "THQ",
+        b"79" => "Accolade",
+        b"80" => "Misawa Entertainment",
+        b"83" => "LOZC",
+        b"86" => "Tokuma Shoten Intermedia",
+        b"87" => "Tsukuda Original",
+        b"91" => "Chunsoft",
+        b"92" => "Video System",
+        b"93" => "Ocean / Acclaim",
+        b"95" => "Varie",
+        b"96" => "Yonezawa/S'Pal",
+        b"97" => "Kaneko",
+        b"99" => "Pack-In-Video",
+        b"9H" => "Bottom Up",
+        b"A4" => "Konami",
+        b"BL" => "MTO",
+        b"DK" => "Kodansha",
+
+        _ => return None
+    })
+}
@@ -1,39 +1,70 @@
+pub mod header;
+pub mod metadata;
+pub mod multicart;
+
 use std::fmt;
 
+use header::{HeaderError, HeaderWarning, MIN_ROM_SIZE, NINTENDO_LOGO};
+use metadata::{CartridgeType, CgbSupport, Destination, Licensee, RomSize, SgbSupport};
+
 
 pub struct Cartridge {
 
     pub rom: Vec<u8>,
 
     // Meta-data extracted from above ROM
-    entry_point: usize,             // 0x0100 - 0x0103
-    nintendo_logo: Vec<u8>,         // 0x0104 - 0x0133
-    title: Box<str>,                // 0x0134 - 0x0143
-    manufacturer_code: Box<str>,    // 0x013F - 0x0142
-    cgb_flag: u8,                   // 0x0143
-    new_licensee_code: u16,         // 0x0144 - 0x0145
-    sgb_flag: u8,                   // 0x0146
-    cartridge_type: u8,             // 0x0147
-    rom_size: u8,                   // 0x0148
-    ram_size: u8,                   // 0x0149
-    destination_code: u8,           // 0x014A
-    old_licensee_code: u8,          // 0x014B
-    mask_rom_version_number: u8,    // 0x014C
-    header_checksum: u8,            // 0x014D
-    global_checksum: u16            // 0x014E - 0x014F
+    entry_point: usize,                     // 0x0100 - 0x0103
+    nintendo_logo: Vec<u8>,                 // 0x0104 - 0x0133
+    title: Box<str>,                        // 0x0134 - 0x0143 (or - 0x0142, or - 0x013E)
+    manufacturer_code: Option<Box<str>>,    // 0x013F - 0x0142
+    cgb_flag: u8,                           // 0x0143
+    new_licensee_code: [u8; 2],             // 0x0144 - 0x0145
+    sgb_flag: u8,                           // 0x0146
+    cartridge_type: u8,                     // 0x0147
+    rom_size: u8,                           // 0x0148
+    ram_size: u8,                           // 0x0149
+    destination_code: u8,                   // 0x014A
+    old_licensee_code: u8,                  // 0x014B
+    mask_rom_version_number: u8,            // 0x014C
+    header_checksum: u8,                    // 0x014D
+    global_checksum: u16,                   // 0x014E - 0x014F
+
+    // The real mapper of a multicart, whose header only describes its menu
+    multicart: Option<CartridgeType>,
+
+    // Anything found wrong with the header that didn't prevent loading
+    warnings: Vec<HeaderWarning>
 }
 
 
 impl Cartridge {
-    pub fn new(data: Vec<u8>) -> Cartridge {
-        Cartridge {
-            rom: data.clone(),
+
+    /// Parse and validate the header of a ROM image. Only a ROM too small to hold a header is
+    ///  rejected; anything else wrong with it is recorded in `warnings` and loading continues.
+    pub fn parse(data: Vec<u8>) -> Result<Cartridge, HeaderError> {
+
+        if data.len() < MIN_ROM_SIZE {
+            return Err(HeaderError::TooSmall { size: data.len() });
+        }
+
+        // The title shrinks from 16 to 15 bytes when 0x0143 is used as the CGB flag, and again to
+        //  11 bytes when a manufacturer code follows it
+        let (title, manufacturer_code) = match (data[0x0143] & 0x80 != 0, header::has_manufacturer_code(&data)) {
+            (false, _)    => (header::decode_title(&data[0x0134 ..= 0x0143]), None),
+            (true, false) => (header::decode_title(&data[0x0134 ..= 0x0142]), None),
+            (true, true)  => (
+                header::decode_title(&data[0x0134 ..= 0x013E]),
+                Some(header::decode_title(&data[0x013F ..= 0x0142]).into_boxed_str())
+            )
+        };
+
+        let mut cartridge = Cartridge {
             entry_point: 0x0100,
             nintendo_logo: Vec::from(&data[0x0104..=0x0133]),
-            title: Box::from(std::str::from_utf8(&data[0x0134 ..= 0x0142]).unwrap()),
-            manufacturer_code: Box::from(std::str::from_utf8(&data[0x013F ..= 0x0142]).unwrap()),
+            title: title.into_boxed_str(),
+            manufacturer_code,
             cgb_flag: data[0x0143],
-            new_licensee_code: ((data[0x0144] as u16) << 8) | (data[0x0145] as u16),
+            new_licensee_code: [data[0x0144], data[0x0145]],
             sgb_flag: data[0x0146],
             cartridge_type: data[0x0147],
             rom_size: data[0x0148],
@@ -42,8 +73,60 @@ impl Cartridge {
             old_licensee_code: data[0x014B],
             mask_rom_version_number: data[0x014C],
             header_checksum: data[0x014D],
-            global_checksum: ((data[0x014E] as u16) << 8) | data[0x0150] as u16
+            global_checksum: ((data[0x014E] as u16) << 8) | data[0x014F] as u16,
+            multicart: multicart::detect(&data),
+            warnings: Vec::new(),
+            rom: data
+        };
+
+        cartridge.warnings = cartridge.validate();
+
+        Ok(cartridge)
+    }
+
+    /// Check the header against the ROM it came from
+    fn validate(&self) -> Vec<HeaderWarning> {
+
+        let mut warnings = Vec::new();
+
+        if self.nintendo_logo != NINTENDO_LOGO {
+            warnings.push(HeaderWarning::LogoMismatch);
+        }
+
+        let expected = header::header_checksum(&self.rom);
+        if expected != self.header_checksum {
+            warnings.push(HeaderWarning::HeaderChecksumMismatch { expected, actual: self.header_checksum });
+        }
+
+        let expected = header::global_checksum(&self.rom);
+        if expected != self.global_checksum {
+            warnings.push(HeaderWarning::GlobalChecksumMismatch { expected, actual: self.global_checksum });
         }
+
+        match RomSize::from_code(self.rom_size).map(|size| size.bytes) {
+            None => warnings.push(HeaderWarning::UnknownRomSize { code: self.rom_size }),
+            Some(expected) if expected != self.rom.len() => {
+                warnings.push(HeaderWarning::RomSizeMismatch { expected, actual: self.rom.len() })
+            }
+            Some(_) => ()
+        };
+
+        warnings
+    }
+
+    /// The game's title, with any padding removed
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// The 4-character manufacturer code, which only some later cartridges have
+    pub fn manufacturer_code(&self) -> Option<&str> {
+        self.manufacturer_code.as_deref()
+    }
+
+    /// Problems found with the header that did not stop the cartridge from loading
+    pub fn warnings(&self) -> &[HeaderWarning] {
+        &self.warnings
     }
 
     pub fn cartridge_type(&self) -> u8 {
@@ -53,11 +136,54 @@ impl Cartridge {
     pub fn rom_size(&self) -> u8 {
         self.rom_size
     }
+
+    /// The mapper and extra hardware on the cartridge: what the header declares, unless it's a
+    ///  multicart whose header can't be taken at its word
+    pub fn kind(&self) -> CartridgeType {
+        self.multicart.unwrap_or_else(|| CartridgeType::from_code(self.cartridge_type))
+    }
+
+    /// The declared ROM size, if the size code is a known one
+    pub fn rom_size_info(&self) -> Option<RomSize> {
+        RomSize::from_code(self.rom_size)
+    }
+
+    /// The declared external RAM size in bytes, if the size code is a known one
+    pub fn ram_size_bytes(&self) -> Option<usize> {
+        metadata::ram_size_bytes(self.ram_size)
+    }
+
+    pub fn licensee(&self) -> Licensee {
+        Licensee::from_codes(self.old_licensee_code, self.new_licensee_code)
+    }
+
+    pub fn destination(&self) -> Destination {
+        Destination::from_code(self.destination_code)
+    }
+
+    pub fn cgb_support(&self) -> CgbSupport {
+        CgbSupport::from_code(self.cgb_flag)
+    }
+
+    pub fn sgb_support(&self) -> SgbSupport {
+        SgbSupport::from_codes(self.sgb_flag, self.old_licensee_code)
+    }
 }
 
 
 impl fmt::Debug for Cartridge {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+
+        let rom_size = match self.rom_size_info() {
+            Some(size) => format!("{} banks, {} KiB", size.banks, size.bytes / 1024),
+            None => String::from("Unknown")
+        };
+
+        let ram_size = match self.ram_size_bytes() {
+            Some(bytes) => format!("{} KiB", bytes / 1024),
+            None => String::from("Unknown")
+        };
+
         write!(f, "\
             Cartridge Data\n\
             =========================\n\
@@ -65,20 +191,123 @@ impl fmt::Debug for Cartridge {
             Nintendo Logo: {:02X?}\n\
             Title: {}\n\
             Manufacturer Code: {}\n\
-            CGB Flag: {}\n\
-            New Licensee Code: {}\n\
-            SGB Flag: {}\n\
-            Cartridge Type: {}\n\
-            ROM Size: {}\n\
-            RAM Size: {}\n\
-            Destination Code: {}\n\
-            Old Licensee Code: {}\n\
+            Licensee: {}\n\
+            CGB Support: {:?} ({:#04X})\n\
+            SGB Support: {:?} ({:#04X})\n\
+            Cartridge Type: {} ({:#04X})\n\
+            ROM Size: {} ({:#04X})\n\
+            RAM Size: {} ({:#04X})\n\
+            Destination: {:?} ({:#04X})\n\
             Mask ROM V. Number: {}\n\
-            Header Checksum: {}\n\
-            Global Checksum: {}
-        ", self.entry_point, self.nintendo_logo, self.title, self.manufacturer_code, self.cgb_flag,
-               self.new_licensee_code, self.sgb_flag, self.cartridge_type, self.rom_size,
-               self.ram_size, self.destination_code, self.old_licensee_code,
-               self.mask_rom_version_number, self.header_checksum, self.global_checksum)
-    }
-}
\ No newline at end of file
+            Header Checksum: {:#04X}\n\
+            Global Checksum: {:#06X}
+        ", self.entry_point, self.nintendo_logo, self.title, self.manufacturer_code().unwrap_or("-"),
+               self.licensee(), self.cgb_support(), self.cgb_flag, self.sgb_support(), self.sgb_flag,
+               self.kind(), self.cartridge_type, rom_size, self.rom_size, ram_size, self.ram_size,
+               self.destination(), self.destination_code, self.mask_rom_version_number,
+               self.header_checksum, self.global_checksum)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::cartridge::Cartridge;
+    use crate::cartridge::header::{HeaderError, HeaderWarning};
+    use crate::cartridge::metadata::{CgbSupport, Destination, Licensee, Mapper, RomSize, SgbSupport};
+    use crate::testing::rom;
+
+    #[test]
+    fn valid_header() {
+        let cartridge = Cartridge::parse(rom(0x01, 0x01, 0x00, &[])).unwrap();
+        assert_eq!(cartridge.title(), "TEST");
+        assert_eq!(cartridge.manufacturer_code(), None);
+        assert!(cartridge.warnings().is_empty());
+    }
+
+    #[test]
+    fn too_small() {
+        assert_eq!(Cartridge::parse(vec![0; 0x0150]).err(), Some(HeaderError::TooSmall { size: 0x0150 }));
+    }
+
+    #[test]
+    fn warnings_do_not_prevent_loading() {
+        let mut data = rom(0x00, 0x00, 0x00, &[]);
+        data[0x0104] = 0x00;        // Logo
+        data[0x0148] = 0x01;        // 64 KiB declared for a 32 KiB ROM (and so both checksums)
+
+        let warnings = Cartridge::parse(data).unwrap().warnings().to_vec();
+
+        assert_eq!(warnings.len(), 4);
+        assert_eq!(warnings[0], HeaderWarning::LogoMismatch);
+        assert!(matches!(warnings[1], HeaderWarning::HeaderChecksumMismatch { .. }));
+        assert!(matches!(warnings[2], HeaderWarning::GlobalChecksumMismatch { .. }));
+        assert_eq!(warnings[3], HeaderWarning::RomSizeMismatch { expected: 0x10000, actual: 0x8000 });
+    }
+
+    #[test]
+    fn bad_header_checksum() {
+        let mut data = rom(0x00, 0x00, 0x00, &[]);
+        data[0x014D] = data[0x014D].wrapping_add(1);
+
+        let cartridge = Cartridge::parse(data).unwrap();
+        assert!(matches!(cartridge.warnings()[0], HeaderWarning::HeaderChecksumMismatch { .. }));
+    }
+
+    #[test]
+    fn title_variants() {
+
+        // Old 16-byte title, running into the (then unused) CGB flag
+        let mut data = rom(0x00, 0x00, 0x00, &[]);
+        data[0x0134..=0x0143].copy_from_slice(b"SIXTEEN BYTES AB");
+        assert_eq!(Cartridge::parse(data).unwrap().title(), "SIXTEEN BYTES AB");
+
+        // CGB flag with a 15-byte title; non-ASCII bytes are replaced, not rejected
+        let mut data = rom(0x00, 0x00, 0x00, &[]);
+        data[0x0134..=0x0143].copy_from_slice(b"POKEMON\xB0\0\0\0\0\0\0\0\xC0");
+        assert_eq!(Cartridge::parse(data).unwrap().title(), "POKEMON\u{FFFD}");
+
+        // CGB flag with an 11-byte title and a manufacturer code
+        let mut data = rom(0x00, 0x00, 0x00, &[]);
+        data[0x0134..=0x0143].copy_from_slice(b"POKEMON_SLVAAXE\x80");
+        let cartridge = Cartridge::parse(data).unwrap();
+        assert_eq!(cartridge.title(), "POKEMON_SLV");
+        assert_eq!(cartridge.manufacturer_code(), Some("AAXE"));
+    }
+
+    #[test]
+    fn decoded_metadata() {
+        let mut data = rom(0x10, 0x06, 0x03, &[]);
+        data[0x0143] = 0xC0;
+        data[0x0144..=0x0145].copy_from_slice(b"01");
+        data[0x0146] = 0x03;
+        data[0x014A] = 0x01;
+        data[0x014B] = 0x33;
+
+        let cartridge = Cartridge::parse(data).unwrap();
+        let kind = cartridge.kind();
+
+        assert_eq!(kind.mapper, Mapper::MBC3);
+        assert!(kind.features.timer && kind.features.ram && kind.features.battery && !kind.features.rumble);
+        assert_eq!(cartridge.rom_size_info(), Some(RomSize { banks: 128, bytes: 0x200000 }));
+        assert_eq!(cartridge.ram_size_bytes(), Some(0x8000));
+        assert_eq!(cartridge.licensee(), Licensee::New { code: *b"01", name: Some("Nintendo") });
+        assert_eq!(cartridge.destination(), Destination::Overseas);
+        assert_eq!(cartridge.cgb_support(), CgbSupport::Only);
+        assert_eq!(cartridge.sgb_support(), SgbSupport::Supported);
+    }
+
+    #[test]
+    fn old_licensee_disables_sgb() {
+        let mut data = rom(0x00, 0x00, 0x00, &[]);
+        data[0x0146] = 0x03;
+        data[0x014B] = 0x01;
+
+        let cartridge = Cartridge::parse(data).unwrap();
+        assert_eq!(cartridge.licensee().name(), Some("Nintendo"));
+        assert_eq!(cartridge.sgb_support(), SgbSupport::None);
+        assert_eq!(cartridge.kind().mapper, Mapper::RomOnly);
+        assert_eq!(cartridge.destination(), Destination::Japanese);
+    }
+}
@@ -0,0 +1,129 @@
+use super::header::{MIN_ROM_SIZE, NINTENDO_LOGO};
+use super::metadata::{CartridgeType, Features, Mapper};
+
+
+/// Multicarts whose mapper can't be told from their contents at all, by the CRC-32 of the whole
+///  ROM. Only dumps that have been checked against the physical cartridge belong here; none have
+///  been yet, so for now every multicart is found by the heuristics below.
+const KNOWN_MULTICARTS: &[(u32, Mapper)] = &[];
+
+// Unlicensed Wisdom Tree cartridges carry the publisher's name in the first bank
+const WISDOM_TREE_MARKERS: &[&[u8]] = &[b"WISDOM TREE", b"WISDOM\0TREE"];
+
+// The M161 (as used by Mani 4 in 1) switches between eight 32 KiB games
+const M161_ROM_SIZE: usize = 8 * MIN_ROM_SIZE;
+
+
+/// Work out the real mapper of a multicart, whose header at 0x0147 only describes the menu (or
+/// the first game); None for an ordinary cartridge. In order:
+///  - ROMs in the known multicart database
+///  - MMM01 cartridges boot from their last 32 KiB, so that's where their own header is
+///  - ROM-only headers on ROMs far larger than 32 KiB: Wisdom Tree if the publisher's name is
+///    present, M161 if the ROM is exactly eight games in size
+pub fn detect(data: &[u8]) -> Option<CartridgeType> {
+
+    if let Some(mapper) = known_multicart(data, KNOWN_MULTICARTS) {
+        return Some(CartridgeType { mapper, features: Features::default() });
+    }
+
+    if data.len() > MIN_ROM_SIZE {
+        let menu = &data[data.len() - MIN_ROM_SIZE..];
+        if menu[0x0104..=0x0133] == NINTENDO_LOGO {
+            let kind = CartridgeType::from_code(menu[0x0147]);
+            if kind.mapper == Mapper::MMM01 {
+                return Some(kind);
+            }
+        }
+    }
+
+    if data[0x0147] == 0x00 && data.len() > MIN_ROM_SIZE {
+
+        let first_bank = &data[..0x4000];
+        let wisdom_tree = WISDOM_TREE_MARKERS.iter()
+            .any(|marker| first_bank.windows(marker.len()).any(|window| window == *marker));
+
+        let mapper = match (wisdom_tree, data.len()) {
+            (true, _) => Mapper::WisdomTree,
+            (false, M161_ROM_SIZE) => Mapper::M161,
+            _ => return None
+        };
+
+        return Some(CartridgeType { mapper, features: Features::default() });
+    }
+
+    None
+}
+
+
+/// Look a ROM up in a multicart database; the checksum is only worked out when there is
+///  something to compare it against, since it means reading the whole ROM
+fn known_multicart(data: &[u8], known: &[(u32, Mapper)]) -> Option<Mapper> {
+    match known {
+        [] => None,
+        known => {
+            let checksum = crc32(data);
+            known.iter().find(|(crc, _)| *crc == checksum).map(|(_, mapper)| *mapper)
+        }
+    }
+}
+
+
+/// The CRC-32 (as used by zip, and so by ROM databases) of some data
+pub fn crc32(data: &[u8]) -> u32 {
+    !data.iter().fold(!0u32, |crc, byte| {
+        (0..8).fold(crc ^ *byte as u32, |crc, _| match crc & 1 {
+            1 => (crc >> 1) ^ 0xEDB8_8320,
+            _ => crc >> 1
+        })
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::cartridge::metadata::Mapper;
+    use crate::cartridge::multicart::{crc32, detect, known_multicart};
+    use crate::testing::rom;
+
+    #[test]
+    fn crc() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn known_hashes() {
+
+        // A multicart nothing about its contents gives away, listed by its checksum
+        let data = rom(0x00, 0x02, 0x00, &[]);
+        let known = [(0x1234_5678, Mapper::MMM01), (crc32(&data), Mapper::M161)];
+        assert_eq!(known_multicart(&data, &known), Some(Mapper::M161));
+        assert_eq!(known_multicart(&data, &known[..1]), None);
+        assert_eq!(known_multicart(&data, &[]), None);
+    }
+
+    #[test]
+    fn detection() {
+
+        // An ordinary cartridge, and an MBC1 game that happens to be larger than 32 KiB
+        assert_eq!(detect(&rom(0x00, 0x00, 0x00, &[])), None);
+        assert_eq!(detect(&rom(0x01, 0x02, 0x00, &[])), None);
+
+        // MMM01: the menu's header sits in the last 32 KiB
+        let mut data = rom(0x01, 0x03, 0x00, &[]);
+        let menu = rom(0x0D, 0x00, 0x03, &[]);
+        let start = data.len() - menu.len();
+        data[start..].copy_from_slice(&menu);
+        let kind = detect(&data).unwrap();
+        assert_eq!(kind.mapper, Mapper::MMM01);
+        assert!(kind.features.battery);
+
+        // A ROM-only header on 256 KiB of ROM, with and without Wisdom Tree's name
+        let mut data = rom(0x00, 0x00, 0x00, &[]);
+        data.resize(0x40000, 0);
+        assert_eq!(detect(&data).unwrap().mapper, Mapper::M161);
+
+        data[0x0200..0x020B].copy_from_slice(b"WISDOM TREE");
+        assert_eq!(detect(&data).unwrap().mapper, Mapper::WisdomTree);
+    }
+}
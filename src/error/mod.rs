@@ -0,0 +1,60 @@
+use std::fmt;
+
+use crate::cartridge::header::HeaderError;
+
+
+/// Everything that can go wrong loading or running a cartridge. These are returned rather than
+/// panicking, so that a bad ROM stops only its own emulation and not the whole process.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EmulatorError {
+
+    /// The cartridge header couldn't be parsed
+    Header(HeaderError),
+
+    /// The cartridge type (0x0147) uses a memory bank controller that isn't supported
+    UnsupportedMapper { cartridge_type: u8 },
+
+    /// The RAM size code (0x0149) isn't one of the known values
+    InvalidRamSize { code: u8 },
+
+    /// A boot ROM image is neither 256 bytes (DMG0, DMG, MGB, SGB) nor 2304 bytes (CGB)
+    InvalidBootRom { size: usize },
+
+    /// The CPU fetched an opcode that doesn't exist on the LR35902
+    InvalidOpcode { opcode: u8, cb_prefixed: bool, pc: u16 }
+}
+
+
+impl fmt::Display for EmulatorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EmulatorError::Header(err) =>
+                write!(f, "invalid cartridge header: {}", err),
+
+            EmulatorError::UnsupportedMapper { cartridge_type } =>
+                write!(f, "unsupported cartridge type: {:#04X}", cartridge_type),
+
+            EmulatorError::InvalidRamSize { code } =>
+                write!(f, "invalid RAM size code: {:#04X}", code),
+
+            EmulatorError::InvalidBootRom { size } =>
+                write!(f, "invalid boot ROM: {} bytes, expected 256 or 2304", size),
+
+            EmulatorError::InvalidOpcode { opcode, cb_prefixed: false, pc } =>
+                write!(f, "invalid opcode {:#04X} at PC {:#06X}", opcode, pc),
+
+            EmulatorError::InvalidOpcode { opcode, cb_prefixed: true, pc } =>
+                write!(f, "invalid opcode 0xCB {:#04X} at PC {:#06X}", opcode, pc)
+        }
+    }
+}
+
+
+impl std::error::Error for EmulatorError {}
+
+
+impl From<HeaderError> for EmulatorError {
+    fn from(err: HeaderError) -> EmulatorError {
+        EmulatorError::Header(err)
+    }
+}
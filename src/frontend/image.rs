@@ -0,0 +1,144 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::mmu::mbc::camera::SensorImage;
+
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1A\n";
+
+
+/// Load a picture for a Pocket Camera to look at, from a PGM file (binary or plain), or a PNG if
+/// built with the camera-png feature. Colour pictures are converted to greyscale, and any size is
+/// stretched to fit the sensor.
+pub fn load(path: &Path) -> io::Result<SensorImage> {
+    let data = fs::read(path)?;
+
+    match &data[..] {
+        [b'P', b'2', ..] | [b'P', b'5', ..] => parse_pgm(&data),
+        png if png.starts_with(PNG_SIGNATURE) => parse_png(png),
+        _ => Err(invalid("not a PGM or PNG image"))
+    }
+}
+
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+
+/// Parse a PGM image: a header of whitespace-separated fields (with # comments), then the
+///  pixels, either as bytes (P5, two big-endian bytes each if the maximum is over 255) or as
+///  decimal numbers (P2)
+fn parse_pgm(data: &[u8]) -> io::Result<SensorImage> {
+
+    // The magic number, width, height and maximum value
+    let mut fields = Vec::new();
+    let mut position = 0;
+    while fields.len() < 4 {
+        match data.get(position) {
+            Some(b'#') => while position < data.len() && data[position] != b'\n' { position += 1 },
+            Some(byte) if byte.is_ascii_whitespace() => position += 1,
+            Some(_) => {
+                let start = position;
+                while position < data.len() && !data[position].is_ascii_whitespace() { position += 1 }
+                fields.push(&data[start..position]);
+            },
+            None => return Err(invalid("truncated PGM header"))
+        }
+    }
+
+    let number = |field: &[u8]| std::str::from_utf8(field).ok()
+        .and_then(|field| field.parse::<usize>().ok())
+        .ok_or_else(|| invalid("invalid PGM header"));
+    let (width, height, maximum) = (number(fields[1])?, number(fields[2])?, number(fields[3])?);
+
+    if width == 0 || height == 0 || maximum == 0 || maximum > 0xFFFF {
+        return Err(invalid("invalid PGM header"));
+    }
+
+    // The size comes straight from the file, so it can't be trusted not to overflow
+    let size = width.checked_mul(height).ok_or_else(|| invalid("invalid PGM header"))?;
+
+    // A single whitespace character separates the header from binary pixels
+    let body = data.get(position + 1..).unwrap_or(&[]);
+
+    let samples: Vec<usize> = match (fields[0], maximum) {
+        (b"P5", 0..=0xFF) => body.iter().map(|&byte| byte as usize).collect(),
+        (b"P5", _) => body.chunks_exact(2).map(|pair| (pair[0] as usize) << 8 | pair[1] as usize).collect(),
+        _ => body.split(|byte| byte.is_ascii_whitespace())
+            .filter(|field| !field.is_empty())
+            .map(number)
+            .collect::<io::Result<_>>()?
+    };
+
+    if samples.len() < size {
+        return Err(invalid("truncated PGM image"));
+    }
+
+    let pixels: Vec<u8> = samples[..size].iter()
+        .map(|&sample| (sample.min(maximum) * 255 / maximum) as u8)
+        .collect();
+
+    Ok(SensorImage::from_luma(width, height, &pixels))
+}
+
+
+#[cfg(feature = "camera-png")]
+fn parse_png(data: &[u8]) -> io::Result<SensorImage> {
+
+    let mut decoder = png::Decoder::new(data);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+
+    let mut reader = decoder.read_info().map_err(|err| invalid(&err.to_string()))?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let frame = reader.next_frame(&mut buffer).map_err(|err| invalid(&err.to_string()))?;
+
+    // Indexed images have been expanded to RGB, and 16-bit samples cut to 8
+    let channels = frame.color_type.samples();
+    let pixels: Vec<u8> = buffer[..frame.buffer_size()]
+        .chunks_exact(channels)
+        .map(|pixel| match channels {
+            1 | 2 => pixel[0],
+            _ => ((pixel[0] as u32 * 299 + pixel[1] as u32 * 587 + pixel[2] as u32 * 114) / 1000) as u8
+        })
+        .collect();
+
+    Ok(SensorImage::from_luma(frame.width as usize, frame.height as usize, &pixels))
+}
+
+
+#[cfg(not(feature = "camera-png"))]
+fn parse_png(_data: &[u8]) -> io::Result<SensorImage> {
+    Err(invalid("PNG images need the camera-png feature; use a PGM image instead"))
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::frontend::image::parse_pgm;
+    use crate::mmu::mbc::camera::{SensorImage, SENSOR_WIDTH, SENSOR_HEIGHT};
+
+    #[test]
+    fn pgm() {
+
+        // 2 x 2, black and white columns, stretched over the sensor
+        let expected = SensorImage::from_luma(2, 2, &[0, 255, 0, 255]);
+        assert_eq!(expected.pixel(0, 0), 0);
+        assert_eq!(expected.pixel(SENSOR_WIDTH - 1, SENSOR_HEIGHT - 1), 255);
+
+        let plain = b"P2\n# a comment\n2 2\n15\n0 15\n0 15\n";
+        assert_eq!(parse_pgm(plain).unwrap(), expected);
+
+        let binary = b"P5 2 2 255\n\x00\xFF\x00\xFF";
+        assert_eq!(parse_pgm(binary).unwrap(), expected);
+
+        let wide = b"P5 2 2 65535\n\x00\x00\xFF\xFF\x00\x00\xFF\xFF";
+        assert_eq!(parse_pgm(wide).unwrap(), expected);
+
+        assert!(parse_pgm(b"P5 2 2 255\n\x00").is_err());
+        assert!(parse_pgm(b"P5 2").is_err());
+        assert!(parse_pgm(b"P5 4294967296 4294967296 255\n\x00").is_err());
+    }
+}
@@ -0,0 +1,10 @@
+//! Frontends drive the emulation core and present its output; the core itself never depends on
+//! any of them, so it can be built and run headless.
+
+pub mod image;
+pub mod null;
+pub mod recording;
+pub mod save;
+
+#[cfg(feature = "sdl")]
+pub mod sdl;
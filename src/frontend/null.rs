@@ -0,0 +1,18 @@
+use crate::joypad::Buttons;
+use crate::traits::Frontend;
+
+
+/// A frontend that discards all output and never presses any buttons
+pub struct NullFrontend;
+
+
+impl Frontend for NullFrontend {
+
+    fn present_frame(&mut self, _framebuffer: &[u8]) { }
+
+    fn push_audio_samples(&mut self, _samples: &[i16]) { }
+
+    fn poll_buttons(&mut self) -> Buttons {
+        Buttons::default()
+    }
+}
@@ -0,0 +1,62 @@
+use std::collections::VecDeque;
+
+use crate::joypad::{Buttons, Tilt};
+use crate::traits::Frontend;
+
+
+/// A frontend that keeps every frame, audio sample and rumble state it is given, and plays back a
+/// scripted sequence of button states (one per frame); intended for tests and tooling.
+pub struct RecordingFrontend {
+    pub frames: Vec<Vec<u8>>,
+    pub samples: Vec<i16>,
+    pub rumble: Vec<bool>,
+
+    // Returned by every poll, for cartridges with an accelerometer
+    pub tilt: Tilt,
+
+    script: VecDeque<Buttons>
+}
+
+
+impl RecordingFrontend {
+
+    pub fn new() -> RecordingFrontend {
+        RecordingFrontend {
+            frames: Vec::new(),
+            samples: Vec::new(),
+            rumble: Vec::new(),
+            tilt: Tilt::default(),
+            script: VecDeque::new()
+        }
+    }
+
+    /// Queue button states to be returned by successive polls; once the script runs out, no
+    ///  buttons are pressed
+    pub fn script(&mut self, buttons: impl IntoIterator<Item=Buttons>) {
+        self.script.extend(buttons);
+    }
+}
+
+
+impl Frontend for RecordingFrontend {
+
+    fn present_frame(&mut self, framebuffer: &[u8]) {
+        self.frames.push(framebuffer.to_vec());
+    }
+
+    fn push_audio_samples(&mut self, samples: &[i16]) {
+        self.samples.extend_from_slice(samples);
+    }
+
+    fn poll_buttons(&mut self) -> Buttons {
+        self.script.pop_front().unwrap_or_default()
+    }
+
+    fn poll_tilt(&mut self) -> Tilt {
+        self.tilt
+    }
+
+    fn set_rumble(&mut self, active: bool) {
+        self.rumble.push(active);
+    }
+}
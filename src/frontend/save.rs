@@ -0,0 +1,110 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::gameboy::GameBoy;
+
+
+/// The .sav file kept alongside a ROM for its battery-backed RAM. Frontends load it once at start,
+/// then flush it periodically and at exit; a flush only touches the disk if the save has changed.
+pub struct SaveFile {
+    path: PathBuf,
+
+    // What is known to be on disk, to avoid rewriting an unchanged save
+    written: Option<Vec<u8>>
+}
+
+
+impl SaveFile {
+
+    /// The save for a ROM: the same path, with the extension replaced by .sav
+    pub fn for_rom(rom: &Path) -> SaveFile {
+        SaveFile {
+            path: rom.with_extension("sav"),
+            written: None
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Load the save into the GameBoy's cartridge, if there is one; a missing file is not an
+    ///  error, just a game that hasn't been saved yet
+    pub fn load(&mut self, gameboy: &mut GameBoy) -> io::Result<()> {
+        match fs::read(&self.path) {
+            Ok(data) => {
+                gameboy.load_save_data(&data);
+                self.written = Some(data);
+                Ok(())
+            },
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err)
+        }
+    }
+
+    /// Write the cartridge's save data out, if it has any and it has changed since last written.
+    ///  The file is replaced in one step, so a crash mid-write can't leave a truncated save.
+    pub fn flush(&mut self, gameboy: &GameBoy) -> io::Result<()> {
+
+        let data = match gameboy.save_data() {
+            Some(data) => data,
+            None => return Ok(())
+        };
+
+        if self.written.as_ref() == Some(&data) {
+            return Ok(());
+        }
+
+        let temporary = self.path.with_extension("sav.tmp");
+        fs::write(&temporary, &data)?;
+        fs::rename(&temporary, &self.path)?;
+
+        self.written = Some(data);
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use std::fs;
+
+    use crate::cartridge::Cartridge;
+    use crate::frontend::save::SaveFile;
+    use crate::gameboy::GameBoy;
+    use crate::testing::rom;
+
+    #[test]
+    fn round_trip() {
+
+        let path = std::env::temp_dir().join(format!("rustboy-save-{}.gb", std::process::id()));
+
+        // LD A, 0x0A; LD (0x0000), A; LD A, 0x42; LD (0xA123), A; JR -2
+        let program = [0x3E, 0x0A, 0xEA, 0x00, 0x00, 0x3E, 0x42, 0xEA, 0x23, 0xA1, 0x18, 0xFE];
+        let cartridge = Cartridge::parse(rom(0x03, 0x00, 0x02, &program)).unwrap();
+        let mut gameboy = GameBoy::new(cartridge).unwrap();
+        for _ in 0..10 {
+            gameboy.step().unwrap();
+        }
+
+        let mut save = SaveFile::for_rom(&path);
+        save.flush(&gameboy).unwrap();
+
+        let data = fs::read(save.path()).unwrap();
+        assert_eq!(data.len(), 0x2000);
+        assert_eq!(data[0x0123], 0x42);
+
+        // No battery, no save
+        let mut gameboy = GameBoy::new(Cartridge::parse(rom(0x02, 0x00, 0x02, &[])).unwrap()).unwrap();
+        gameboy.load_save_data(&data);
+        assert_eq!(gameboy.save_data(), None);
+
+        let mut gameboy = GameBoy::new(Cartridge::parse(rom(0x03, 0x00, 0x02, &[])).unwrap()).unwrap();
+        SaveFile::for_rom(&path).load(&mut gameboy).unwrap();
+        assert_eq!(gameboy.save_data(), Some(data));
+
+        fs::remove_file(save.path()).unwrap();
+    }
+}
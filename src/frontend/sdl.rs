@@ -0,0 +1,211 @@
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use sdl2::audio::{AudioQueue, AudioSpecDesired};
+use sdl2::event::Event;
+use sdl2::keyboard::Keycode;
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::render::{TextureCreator, WindowCanvas};
+use sdl2::video::WindowContext;
+use sdl2::EventPump;
+
+use crate::frontend::save::SaveFile;
+use crate::gameboy::GameBoy;
+use crate::joypad::{Buttons, Tilt};
+use crate::ppu::{HEIGHT, WIDTH};
+use crate::ppu::display::BYTES_PER_PIXEL;
+use crate::sound::SAMPLE_RATE;
+use crate::traits::Frontend;
+
+// TODO - Configurable Keymap
+const  D_PAD_DOWN: Keycode = Keycode::S;
+const    D_PAD_UP: Keycode = Keycode::W;
+const  D_PAD_LEFT: Keycode = Keycode::A;
+const D_PAD_RIGHT: Keycode = Keycode::D;
+
+const  START: Keycode = Keycode::F;
+const SELECT: Keycode = Keycode::G;
+const  BTN_B: Keycode = Keycode::J;
+const  BTN_A: Keycode = Keycode::I;
+
+// The arrow keys tilt the GameBoy, for cartridges with an accelerometer
+const  TILT_DOWN: Keycode = Keycode::Down;
+const    TILT_UP: Keycode = Keycode::Up;
+const  TILT_LEFT: Keycode = Keycode::Left;
+const TILT_RIGHT: Keycode = Keycode::Right;
+
+/// Factor by which the 160x144 screen is enlarged in the window
+const SCALE: u32 = 3;
+
+/// The GameBoy draws ~59.7 frames per second
+const FRAME_DURATION: Duration = Duration::from_nanos(16_742_706);
+
+/// Stop queueing audio once this many bytes (~0.1s of stereo i16) are waiting to be played, so
+/// that sound doesn't drift behind the picture
+const MAX_QUEUED_AUDIO: u32 = (SAMPLE_RATE / 10) as u32 * 4;
+
+/// Flush the save file roughly every 5 seconds, so a crash loses little progress
+const FRAMES_PER_SAVE: u64 = 300;
+
+
+pub struct SDLFrontend {
+    canvas: WindowCanvas,
+    texture_creator: TextureCreator<WindowContext>,
+    event_pump: EventPump,
+    audio: AudioQueue<i16>,
+
+    closed: bool,
+    rumble: bool
+}
+
+
+impl SDLFrontend {
+
+    pub fn new() -> Result<SDLFrontend, String> {
+
+        let sdl_context = sdl2::init()?;
+        let video_subsystem = sdl_context.video()?;
+        let audio_subsystem = sdl_context.audio()?;
+
+        let canvas = video_subsystem
+            .window("Rustboy", WIDTH as u32 * SCALE, HEIGHT as u32 * SCALE)
+            .position_centered().opengl().build().map_err(|e| e.to_string())?
+            .into_canvas().build().map_err(|e| e.to_string())?;
+
+        let audio = audio_subsystem.open_queue::<i16, _>(None, &AudioSpecDesired {
+            freq: Some(SAMPLE_RATE as i32),
+            channels: Some(2),
+            samples: None
+        })?;
+        audio.resume();
+
+        let texture_creator = canvas.texture_creator();
+        let event_pump = sdl_context.event_pump()?;
+
+        Ok(SDLFrontend {
+            canvas,
+            texture_creator,
+            event_pump,
+            audio,
+            closed: false,
+            rumble: false
+        })
+    }
+
+    /// Whether the window has been closed
+    pub fn closed(&self) -> bool {
+        self.closed
+    }
+
+    fn draw(&mut self, framebuffer: &[u8]) -> Result<(), String> {
+
+        let mut texture = self.texture_creator
+            .create_texture_static(PixelFormatEnum::RGB24, WIDTH as u32, HEIGHT as u32)
+            .map_err(|e| e.to_string())?;
+
+        texture.update(None, framebuffer, WIDTH * BYTES_PER_PIXEL).map_err(|e| e.to_string())?;
+
+        self.canvas.clear();
+        self.canvas.copy(&texture, None, None)?;
+        self.canvas.present();
+
+        Ok(())
+    }
+}
+
+
+impl Frontend for SDLFrontend {
+
+    fn present_frame(&mut self, framebuffer: &[u8]) {
+        if let Err(err) = self.draw(framebuffer) {
+            eprintln!("failed to draw frame: {}", err);
+        }
+    }
+
+    fn push_audio_samples(&mut self, samples: &[i16]) {
+        if self.audio.size() < MAX_QUEUED_AUDIO {
+            self.audio.queue(samples);
+        }
+    }
+
+    /// There's no motor to drive, so show the rumble in the window title instead
+    fn set_rumble(&mut self, active: bool) {
+        if active != self.rumble {
+            self.rumble = active;
+
+            let title = if active { "Rustboy [rumble]" } else { "Rustboy" };
+            if let Err(err) = self.canvas.window_mut().set_title(title) {
+                eprintln!("failed to set window title: {}", err);
+            }
+        }
+    }
+
+    /// Handle any pending window events, and map the keys currently held on the keyboard onto
+    ///  GameBoy buttons
+    fn poll_buttons(&mut self) -> Buttons {
+
+        for event in self.event_pump.poll_iter() {
+            if let Event::Quit { .. } = event {
+                self.closed = true;
+            }
+        }
+
+        let keys: HashSet<Keycode> = self.event_pump.keyboard_state()
+            .pressed_scancodes().filter_map(Keycode::from_scancode).collect();
+
+        Buttons {
+            up: keys.contains(&D_PAD_UP),
+            down: keys.contains(&D_PAD_DOWN),
+            left: keys.contains(&D_PAD_LEFT),
+            right: keys.contains(&D_PAD_RIGHT),
+
+            a: keys.contains(&BTN_A),
+            b: keys.contains(&BTN_B),
+            start: keys.contains(&START),
+            select: keys.contains(&SELECT)
+        }
+    }
+
+    /// Holding an arrow key tilts the GameBoy by 1g that way
+    fn poll_tilt(&mut self) -> Tilt {
+
+        let keys: HashSet<Keycode> = self.event_pump.keyboard_state()
+            .pressed_scancodes().filter_map(Keycode::from_scancode).collect();
+
+        let axis = |negative, positive| keys.contains(&positive) as i8 as f32 - keys.contains(&negative) as i8 as f32;
+
+        Tilt {
+            x: axis(TILT_LEFT, TILT_RIGHT),
+            y: axis(TILT_UP, TILT_DOWN)
+        }
+    }
+}
+
+
+/// Run the given GameBoy in an SDL window until the window is closed, keeping its save file up
+///  to date along the way
+pub fn run(gameboy: &mut GameBoy, save: &mut SaveFile) -> Result<(), String> {
+
+    let mut frontend = SDLFrontend::new()?;
+    let mut frames = 0u64;
+
+    while !frontend.closed() {
+
+        let started = Instant::now();
+
+        gameboy.run_frame_with(&mut frontend).map_err(|e| e.to_string())?;
+
+        frames += 1;
+        if frames.is_multiple_of(FRAMES_PER_SAVE) {
+            if let Err(err) = save.flush(gameboy) {
+                eprintln!("warning: could not write {}: {}", save.path().display(), err);
+            }
+        }
+
+        if let Some(remaining) = FRAME_DURATION.checked_sub(started.elapsed()) {
+            std::thread::sleep(remaining);
+        }
+    }
+
+    save.flush(gameboy).map_err(|err| format!("could not write {}: {}", save.path().display(), err))
+}
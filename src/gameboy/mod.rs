@@ -0,0 +1,285 @@
+use crate::cartridge::Cartridge;
+use crate::error::EmulatorError;
+use crate::joypad::{Buttons, Tilt};
+use crate::lr35902::LR35902;
+use crate::mmu::boot::BootRom;
+use crate::mmu::mbc::camera::SensorImage;
+use crate::mmu::mbc::rtc::ClockSource;
+use crate::model::Model;
+use crate::traits::Frontend;
+
+
+/// A complete GameBoy: the public face of the emulator for frontends and other crates. This wraps
+/// the LR35902 (and through it, the MMU and every other component) behind a small API to step
+/// the machine, run it a frame at a time, feed it input, and read back the screen.
+pub struct GameBoy {
+    cpu: LR35902
+}
+
+
+impl GameBoy {
+
+    /// Create a GameBoy with the given cartridge inserted, in its post-boot state, of the model
+    ///  the cartridge was made for; fails if the cartridge's memory bank controller isn't
+    ///  supported
+    pub fn new(cartridge: Cartridge) -> Result<GameBoy, EmulatorError> {
+        let model = Model::for_cartridge(&cartridge);
+        GameBoy::with_model(cartridge, model, None)
+    }
+
+    /// Create a GameBoy that runs a boot ROM before handing over to the cartridge, as the real
+    ///  thing does at power on, of the model the boot ROM is for
+    pub fn with_boot_rom(cartridge: Cartridge, boot_rom: BootRom) -> Result<GameBoy, EmulatorError> {
+        let model = Model::for_boot_rom(&boot_rom, &cartridge);
+        GameBoy::with_model(cartridge, model, Some(boot_rom))
+    }
+
+    /// Create a specific model of GameBoy, whatever the cartridge was made for, optionally
+    ///  running a boot ROM first
+    pub fn with_model(cartridge: Cartridge, model: Model, boot_rom: Option<BootRom>) -> Result<GameBoy, EmulatorError> {
+        Ok(GameBoy {
+            cpu: LR35902::new(cartridge, model, boot_rom)?
+        })
+    }
+
+    /// The model of GameBoy being emulated
+    pub fn model(&self) -> Model {
+        self.cpu.mmu.model()
+    }
+
+    /// Run a single CPU instruction (and the rest of the system alongside it); returns the
+    ///  number of clock cycles taken
+    pub fn step(&mut self) -> Result<u64, EmulatorError> {
+        self.cpu.tick()
+    }
+
+    /// Run until the next frame has been drawn; returns the number of clock cycles taken
+    pub fn run_frame(&mut self) -> Result<u64, EmulatorError> {
+        self.cpu.run_frame()
+    }
+
+    /// Run until the next frame has been drawn, connected to the given frontend: the buttons and
+    ///  tilt are polled before the frame, and the frame, its audio and the rumble motor's state handed over
+    ///  once VBlank is reached.
+    ///  Returns the number of clock cycles taken.
+    pub fn run_frame_with(&mut self, frontend: &mut dyn Frontend) -> Result<u64, EmulatorError> {
+
+        self.set_buttons(frontend.poll_buttons());
+        self.set_tilt(frontend.poll_tilt());
+
+        let cycles = self.run_frame()?;
+
+        frontend.push_audio_samples(&self.cpu.mmu.take_audio_samples());
+        frontend.present_frame(self.framebuffer());
+        frontend.set_rumble(self.take_rumble());
+
+        Ok(cycles)
+    }
+
+    /// The most recently drawn frame, as RGB24 bytes in row-major order (160 x 144 pixels)
+    pub fn framebuffer(&self) -> &[u8] {
+        self.cpu.mmu.framebuffer()
+    }
+
+    /// Update how the GameBoy is tilted, for cartridges with an accelerometer
+    pub fn set_tilt(&mut self, tilt: Tilt) {
+        self.cpu.mmu.set_tilt(tilt);
+    }
+
+    /// Change what a Pocket Camera cartridge's sensor sees; it starts out looking at
+    ///  SensorImage::test_pattern
+    pub fn set_sensor_image(&mut self, image: SensorImage) {
+        self.cpu.mmu.set_sensor_image(image);
+    }
+
+    /// Whether a rumble cartridge's motor has been running at any point since last asked
+    pub fn take_rumble(&mut self) -> bool {
+        self.cpu.mmu.take_rumble()
+    }
+
+    /// Update the buttons currently held down
+    pub fn set_buttons(&mut self, buttons: Buttons) {
+        self.cpu.mmu.set_buttons(buttons);
+    }
+
+    /// Choose whether the cartridge's real-time clock (if it has one) follows emulated time, the
+    ///  default, or the host's wall-clock time
+    pub fn set_clock_source(&mut self, source: ClockSource) {
+        self.cpu.mmu.set_clock_source(source);
+    }
+
+    /// The cartridge's battery-backed RAM, in the raw .sav format other emulators use; None if
+    ///  the cartridge has no battery
+    pub fn save_data(&self) -> Option<Vec<u8>> {
+        self.cpu.mmu.save_data()
+    }
+
+    /// Restore battery-backed RAM from a .sav file; best done before running anything
+    pub fn load_save_data(&mut self, data: &[u8]) {
+        self.cpu.mmu.load_save_data(data);
+    }
+
+    /// Direct access to the CPU, for debugging and inspection
+    pub fn cpu(&self) -> &LR35902 {
+        &self.cpu
+    }
+
+    /// Mutable access to the CPU, for debugging and inspection
+    pub fn cpu_mut(&mut self) -> &mut LR35902 {
+        &mut self.cpu
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::cartridge::Cartridge;
+    use crate::error::EmulatorError;
+    use crate::frontend::recording::RecordingFrontend;
+    use crate::gameboy::GameBoy;
+    use crate::mmu::boot::BootRom;
+    use crate::model::Model;
+    use crate::ppu::{HEIGHT, WIDTH};
+    use crate::testing::rom;
+    use crate::traits::MemoryMap;
+
+    #[test]
+    fn run_frame_with_frontend() {
+
+        // JR -2 : spin forever
+        let cartridge = Cartridge::parse(rom(0x00, 0x00, 0x00, &[0x18, 0xFE])).unwrap();
+        let mut gameboy = GameBoy::new(cartridge).unwrap();
+        let mut frontend = RecordingFrontend::new();
+
+        gameboy.run_frame_with(&mut frontend).unwrap();
+        gameboy.run_frame_with(&mut frontend).unwrap();
+
+        assert_eq!(frontend.frames.len(), 2);
+        assert_eq!(frontend.frames[0].len(), WIDTH * HEIGHT as usize * 3);
+
+        assert!(!frontend.samples.is_empty());
+        assert_eq!(frontend.samples.len() % 2, 0);
+    }
+
+    #[test]
+    fn invalid_opcode_is_an_error() {
+
+        // NOP; 0xD3 (which doesn't exist on the LR35902)
+        let cartridge = Cartridge::parse(rom(0x00, 0x00, 0x00, &[0x00, 0xD3])).unwrap();
+        let mut gameboy = GameBoy::new(cartridge).unwrap();
+
+        let error = (0..4).map(|_| gameboy.step()).find_map(Result::err);
+        assert_eq!(error, Some(EmulatorError::InvalidOpcode { opcode: 0xD3, cb_prefixed: false, pc: 0x0151 }));
+    }
+
+    #[test]
+    fn boot_rom_hands_over() {
+
+        // NOPs, then unmap the boot ROM just before 0x0100: LD A, 0x01; LDH (0x50), A
+        let mut boot = vec![0x00; 0x100];
+        boot[0xFC..].copy_from_slice(&[0x3E, 0x01, 0xE0, 0x50]);
+
+        let cartridge = Cartridge::parse(rom(0x00, 0x00, 0x00, &[0x18, 0xFE])).unwrap();
+        let mut gameboy = GameBoy::with_boot_rom(cartridge, BootRom::new(boot).unwrap()).unwrap();
+
+        assert_eq!(gameboy.cpu().registers.pc, 0x0000);
+        assert_eq!(gameboy.cpu_mut().mmu.read(0x00FE), 0xE0);
+        assert_eq!(gameboy.cpu_mut().mmu.read(0x0104), 0xCE);
+
+        while gameboy.cpu().mmu.in_boot_rom() {
+            gameboy.step().unwrap();
+        }
+
+        assert_eq!(gameboy.cpu().registers.pc, 0x0100);
+        assert_eq!(gameboy.cpu_mut().mmu.read(0x00FE), 0x00);
+        assert_eq!(gameboy.cpu_mut().mmu.read(0x0101), 0xC3);
+
+        assert_eq!(BootRom::new(vec![0; 0x200]).err(), Some(EmulatorError::InvalidBootRom { size: 0x200 }));
+    }
+
+    #[test]
+    fn speed_switch() {
+
+        // LD A, 0x01; LDH (0x4D), A; STOP; JR -2
+        let mut data = rom(0x00, 0x00, 0x00, &[0x3E, 0x01, 0xE0, 0x4D, 0x10, 0x00, 0x18, 0xFE]);
+        data[0x0143] = 0x80;
+
+        let mut gameboy = GameBoy::new(Cartridge::parse(data).unwrap()).unwrap();
+        assert_eq!(gameboy.model(), Model::CGB);
+
+        for _ in 0..4 {
+            gameboy.step().unwrap();
+        }
+        assert_eq!(gameboy.cpu_mut().mmu.read(0xFF4D), 0x7F);
+
+        // STOP switches speed, then the CPU sits out the switch
+        gameboy.step().unwrap();
+        assert_eq!(gameboy.cpu_mut().mmu.read(0xFF4D), 0xFE);
+        assert!(gameboy.cpu().mmu.double_speed());
+
+        for _ in 0..2050 {
+            gameboy.step().unwrap();
+        }
+        assert_eq!(gameboy.cpu().registers.pc, 0x0155);
+
+        gameboy.step().unwrap();
+        assert_eq!(gameboy.cpu().registers.pc, 0x0156);
+    }
+
+    #[test]
+    fn rtc_keeps_time() {
+
+        // MBC3 + TIMER + RAM + BATTERY, spinning forever
+        let cartridge = Cartridge::parse(rom(0x10, 0x00, 0x03, &[0x18, 0xFE])).unwrap();
+        let mut gameboy = GameBoy::new(cartridge).unwrap();
+
+        let latched_seconds = |gameboy: &mut GameBoy| {
+            let mmu = &mut gameboy.cpu_mut().mmu;
+            mmu.write(0x0000, 0x0A);
+            mmu.write(0x4000, 0x08);
+            mmu.write(0x6000, 0x00);
+            mmu.write(0x6000, 0x01);
+            mmu.read(0xA000)
+        };
+
+        // A little over a second's worth of frames
+        assert_eq!(latched_seconds(&mut gameboy), 0);
+        for _ in 0..64 {
+            gameboy.run_frame().unwrap();
+        }
+        assert_eq!(latched_seconds(&mut gameboy), 1);
+    }
+
+    #[test]
+    fn camera_capture_time() {
+
+        // POCKET CAMERA with 128 KiB of RAM, spinning forever
+        let cartridge = Cartridge::parse(rom(0xFC, 0x00, 0x04, &[0x18, 0xFE])).unwrap();
+        let mut gameboy = GameBoy::new(cartridge).unwrap();
+
+        // With N set and no exposure, a capture takes 4 * 32446 clock cycles
+        let mmu = &mut gameboy.cpu_mut().mmu;
+        mmu.write(0x4000, 0x10);
+        mmu.write(0xA001, 0x80);
+        mmu.write(0xA002, 0x00);
+        mmu.write(0xA003, 0x00);
+        mmu.write(0xA000, 0x01);
+
+        let mut elapsed = 0;
+        loop {
+            let capturing = gameboy.cpu_mut().mmu.read(0xA000) & 0x01 != 0;
+            assert_eq!(capturing, elapsed < 4 * 32446, "after {} cycles", elapsed);
+            match capturing {
+                true => elapsed += gameboy.step().unwrap(),
+                false => break
+            }
+        }
+    }
+
+    #[test]
+    fn unsupported_mapper_is_an_error() {
+        let cartridge = Cartridge::parse(rom(0xFD, 0x00, 0x00, &[])).unwrap();
+        assert_eq!(GameBoy::new(cartridge).err(), Some(EmulatorError::UnsupportedMapper { cartridge_type: 0xFD }));
+    }
+}
@@ -1,21 +1,33 @@
 use crate::traits::RunComponent;
-use sdl2::EventPump;
-use sdl2::keyboard::{Keycode, Scancode};
 
-// TODO - Configurable Keymap
-const  D_PAD_DOWN: Keycode = Keycode::S;
-const    D_PAD_UP: Keycode = Keycode::W;
-const  D_PAD_LEFT: Keycode = Keycode::A;
-const D_PAD_RIGHT: Keycode = Keycode::D;
 
-const  START: Keycode = Keycode::F;
-const SELECT: Keycode = Keycode::G;
-const  BTN_B: Keycode = Keycode::J;
-const  BTN_A: Keycode = Keycode::I;
+/// The state of every button on the GameBoy; true = held down. A frontend (or test harness) fills
+/// this in, and the Joypad translates it into the P1/JOYP register.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Buttons {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+
+    pub a: bool,
+    pub b: bool,
+    pub start: bool,
+    pub select: bool
+}
+
+
+/// How far the GameBoy is tilted, in g along each axis, for cartridges with an accelerometer (MBC7).
+/// Positive x is tilted to the right, positive y tilted towards the player; level is (0, 0).
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Tilt {
+    pub x: f32,
+    pub y: f32
+}
 
 
 pub struct Joypad {
-    event_pump: EventPump,
+    buttons: Buttons,
 
     // Individual bits of  P1/JOYP - 0xFF00
     bit_5: bool,        // Select Action Buttons
@@ -30,9 +42,9 @@ pub struct Joypad {
 
 impl Joypad {
 
-    pub fn new(event_pump: EventPump) -> Joypad {
+    pub fn new() -> Joypad {
         Joypad {
-            event_pump,
+            buttons: Buttons::default(),
 
             // Note - Bit 1 (True) = Not Pressed, 0 (False) = Pressed
             bit_5: true,
@@ -45,6 +57,11 @@ impl Joypad {
         }
     }
 
+    /// Replace the currently held buttons; takes effect on the next run
+    pub fn set_buttons(&mut self, buttons: Buttons) {
+        self.buttons = buttons;
+    }
+
     pub fn read(&self) -> u8 {
         let mut result = 0;
 
@@ -68,32 +85,23 @@ impl Joypad {
 
 impl RunComponent for Joypad {
     fn run(&mut self, _cpu_clock_cycles: u64) {
-        for event in self.event_pump.poll_iter() {
-            match event {
-                sdl2::event::Event::Quit { .. } => std::process::exit(0),
-                sdl2::event::Event::KeyDown { keycode, keymod, .. } => {
-                    // println!("keycode: {:?}, keymod: {}", keycode, keymod.bits());
-                }
-                _ => {}
-            }
-        }
 
-        let keys: std::collections::HashSet<Keycode> = self.event_pump.keyboard_state().pressed_scancodes().filter_map(Keycode::from_scancode).collect();
+        let keys = self.buttons;
 
-        let bit_3_pressed = keys.contains(&D_PAD_DOWN) && self.bit_4 || keys.contains(&START) && self.bit_5;
+        let bit_3_pressed = keys.down && self.bit_4 || keys.start && self.bit_5;
         if self.bit_3 && bit_3_pressed { self.interrupt = true; }
         self.bit_3 = !bit_3_pressed;
 
-        let bit_2_pressed = keys.contains(&D_PAD_UP) && self.bit_4 || keys.contains(&SELECT) && self.bit_5;
+        let bit_2_pressed = keys.up && self.bit_4 || keys.select && self.bit_5;
         if self.bit_2 && bit_2_pressed { self.interrupt = true; }
         self.bit_2 = !bit_2_pressed;
 
-        let bit_1_pressed = keys.contains(&D_PAD_LEFT) && self.bit_4 || keys.contains(&BTN_B) && self.bit_5;
+        let bit_1_pressed = keys.left && self.bit_4 || keys.b && self.bit_5;
         if self.bit_1 && bit_1_pressed { self.interrupt = true; }
         self.bit_1 = !bit_1_pressed;
 
-        let bit_0_pressed = keys.contains(&D_PAD_RIGHT) && self.bit_4 || keys.contains(&BTN_A) && self.bit_5;
+        let bit_0_pressed = keys.right && self.bit_4 || keys.a && self.bit_5;
         if self.bit_0 && bit_0_pressed { self.interrupt = true; }
         self.bit_0 = !bit_0_pressed;
     }
-}
\ No newline at end of file
+}
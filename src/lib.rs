@@ -0,0 +1,32 @@
+#![allow(clippy::upper_case_acronyms, clippy::new_without_default)]
+
+//! An emulator of the Nintendo GameBoy.
+//!
+//! The [`GameBoy`] facade is the intended entry point; the individual components (CPU, MMU,
+//! PPU, ...) are also public for tools that need to inspect or drive them directly.
+
+pub mod mmu;
+pub mod lr35902;
+pub mod cartridge;
+pub mod ppu;
+pub mod sound;
+pub mod timer;
+pub mod joypad;
+pub mod serial;
+pub mod frontend;
+pub mod gameboy;
+pub mod error;
+pub mod model;
+
+pub mod traits;
+pub mod enums;
+mod testing;
+
+pub use cartridge::Cartridge;
+pub use error::EmulatorError;
+pub use gameboy::GameBoy;
+pub use joypad::{Buttons, Tilt};
+pub use mmu::boot::BootRom;
+pub use mmu::mbc::camera::SensorImage;
+pub use mmu::mbc::rtc::ClockSource;
+pub use model::Model;
@@ -1,3 +1,4 @@
+use crate::error::EmulatorError;
 use crate::traits::MemoryMap;
 use crate::lr35902::LR35902;
 
@@ -6,8 +7,12 @@ impl LR35902 {
 
     #[allow(unreachable_patterns)]
     /// Call the instruction corresponding the given opcode, and return the number of cycles taken
-    pub fn call_instruction(&mut self, code: u8) -> u64 {
-        match self.use_cb_table {
+    pub fn call_instruction(&mut self, code: u8) -> Result<u64, EmulatorError> {
+
+        // The opcode has already been fetched, so the PC has moved past it
+        let pc = self.registers.pc.wrapping_sub(1);
+
+        Ok(match self.use_cb_table {
             // Default LR35902 Opcodes
             false => {
                 match code {
@@ -288,7 +293,7 @@ impl LR35902 {
                     0xFF => self.rst_38h_0xff(),
 
                     // Unmapped code in default table
-                    _ => panic!("Unmapped default table opcode {}", code),
+                    _ => return Err(EmulatorError::InvalidOpcode { opcode: code, cb_prefixed: false, pc }),
                 }
             }
 
@@ -587,10 +592,10 @@ impl LR35902 {
                     0xFF => self.set_7_a_0xcbff(),
 
                     // Unmapped code in CB table
-                    _ => panic!("Unmapped CB table opcode {}", code)
+                    _ => return Err(EmulatorError::InvalidOpcode { opcode: code, cb_prefixed: true, pc })
                 }
             }
-        }
+        })
     }
 
     /*****************************************/
@@ -1082,7 +1087,7 @@ impl LR35902 {
     /*   0x40 - 0x4F   */
     // 0x40 - LD B B
     fn ld_b_b_0x40(&mut self) -> u64 {
-        self.registers.b = self.registers.b; // ah, yes
+        // Loading a register into itself is a no-op
         4
     }
 
@@ -1136,7 +1141,7 @@ impl LR35902 {
 
     // 0x49 - LD C C
     fn ld_c_c_0x49(&mut self) -> u64 {
-        self.registers.c = self.registers.c; // ok
+        // Loading a register into itself is a no-op
         4
     }
 
@@ -1192,7 +1197,7 @@ impl LR35902 {
 
     // 0x52 - LD D, D
     fn ld_d_d_0x52(&mut self) -> u64 {
-        self.registers.d = self.registers.d; //...
+        // Loading a register into itself is a no-op
         4
     }
 
@@ -1245,7 +1250,7 @@ impl LR35902 {
 
     // 0x5B - LD, E, E
     fn ld_e_e_0x5b(&mut self) -> u64 {
-        self.registers.e = self.registers.e; //literally nop
+        // Loading a register into itself is a no-op
         4
     }
 
@@ -1301,7 +1306,7 @@ impl LR35902 {
 
     // 0x64 - LD H H
     fn ld_h_h_0x64(&mut self) -> u64 {
-        self.registers.h = self.registers.h; // sure
+        // Loading a register into itself is a no-op
         4
     }
 
@@ -1355,7 +1360,7 @@ impl LR35902 {
 
     // 0x6D - LD L L
     fn ld_l_l_0x6d(&mut self) -> u64 {
-        self.registers.l = self.registers.l; // ok
+        // Loading a register into itself is a no-op
         4
     }
 
@@ -1467,7 +1472,7 @@ impl LR35902 {
 
     //0x7F - LD A, A
     fn ld_a_a_0x7f(&mut self) -> u64 {
-        self.registers.a = self.registers.a; // I'll never get tired of these lol
+        // Loading a register into itself is a no-op
         4
     }
 
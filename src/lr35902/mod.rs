@@ -4,12 +4,22 @@ mod registers;
 use std::fmt;
 
 use crate::enums::{IME, Status, Status::*};
+use crate::error::EmulatorError;
 use crate::traits::{MemoryMap, RunComponent};
 
 use super::cartridge::Cartridge;
 use super::mmu::MMU;
+use super::mmu::boot::BootRom;
+use super::model::Model;
 use registers::Registers;
 
+
+/// Number of clock cycles to draw a full frame, including VBlank (154 lines * 456 cycles)
+pub const CYCLES_PER_FRAME: u64 = 70224;
+
+/// Number of clock cycles the CPU sits idle for while switching speed on a CGB
+const SPEED_SWITCH_CYCLES: u64 = 8200;
+
 // Struct representing the LR35902 CPU
 pub struct LR35902 {
 
@@ -31,60 +41,111 @@ pub struct LR35902 {
     // CB Flag : Will set whether to use the default table or the CB Prefix table
     use_cb_table: bool,
 
+    // Clock cycles left before the CPU resumes after a STOP switched speed
+    speed_switch_delay: u64,
+
     mooneye_testing: bool
 }
 
 #[allow(dead_code)]
 impl LR35902 {
 
-    /// Initializer for a LR35902 CPU
-    pub fn new(cartridge: Cartridge) -> LR35902 {
-        LR35902 {
-            mmu: MMU::new(cartridge),
-            registers: Registers::new(),
+    /// Initializer for a LR35902 CPU in the given model of GameBoy; fails if the cartridge can't
+    ///  be mapped into memory. With a boot ROM, execution starts at 0x0000 from power on; without
+    ///  one, at 0x0100 in the state the model's boot ROM would leave.
+    pub fn new(cartridge: Cartridge, model: Model, boot_rom: Option<BootRom>) -> Result<LR35902, EmulatorError> {
+
+        let registers = match boot_rom {
+            Some(_) => Registers::power_on(),
+            None => Registers::post_boot(model, &cartridge)
+        };
+
+        Ok(LR35902 {
+            mmu: MMU::new(cartridge, model, boot_rom)?,
+            registers,
             status: RUNNING,
             ime: IME::Disabled,
             clock: 0,
             use_cb_table: false,
+            speed_switch_delay: 0,
             mooneye_testing: false
-        }
+        })
     }
 
-    pub fn testing(cartridge: Cartridge) -> LR35902 {
-        LR35902 {
-            mmu: MMU::new(cartridge),
+    pub fn testing(cartridge: Cartridge) -> Result<LR35902, EmulatorError> {
+        Ok(LR35902 {
+            mmu: MMU::new(cartridge, Model::DMG, None)?,
             registers: Registers::new(),
             status: RUNNING,
             ime: IME::Disabled,
             clock: 0,
             use_cb_table: false,
+            speed_switch_delay: 0,
             mooneye_testing: true
-        }
+        })
     }
 
     /// Run the cycle until otherwise halted / interrupted by an interrupt / exception
-    pub fn run(&mut self) {
+    pub fn run(&mut self) -> Result<(), EmulatorError> {
         loop {
-            self.step();
-
-            let cycles = self.step();
+            let cycles = self.tick()?;
             if cycles == 0 {   // 0 is returned from Mooneye tests when complete
-                break;
+                return Ok(());
             }
+        }
+    }
 
-            // println!("cycles taken: {}", cycles);
+    /// Run until the PPU completes a frame, or a frame's worth of cycles has passed (such as when
+    ///  the LCD is turned off and no frame will ever be completed). Returns the cycles run.
+    pub fn run_frame(&mut self) -> Result<u64, EmulatorError> {
+
+        let mut cycles_run = 0;
+
+        while cycles_run < CYCLES_PER_FRAME {
+            let cycles = self.tick()?;
+            if cycles == 0 {
+                break;
+            }
 
-            // Adjust clock and program counter (PC)
-            self.clock += cycles as u64;
+            // Frames are timed by the normal clock, which runs at half the CPU's in double speed
+            cycles_run += match self.mmu.double_speed() {
+                true => cycles / 2,
+                false => cycles
+            };
 
-            // The PPU runs at a clock rate of 4.2 MHz, while the LR35902 runs at 1.05 MHz
-            //  Each cycle run by the CPU corresponds to 4 PPU cycles
-            self.mmu.run(cycles * 4);
+            if self.mmu.take_frame_ready() {
+                break;
+            }
         }
+
+        Ok(cycles_run)
+    }
+
+    /// Run one step of the CPU, and the rest of the system for the cycles that step took
+    pub fn tick(&mut self) -> Result<u64, EmulatorError> {
+
+        // The rest of the system also runs for any time the CPU was held up by VRAM DMA
+        let cycles = self.step()? + self.mmu.take_dma_cycles();
+
+        // Adjust clock and program counter (PC)
+        self.clock += cycles;
+
+        // The PPU runs at a clock rate of 4.2 MHz, while the LR35902 runs at 1.05 MHz
+        //  Each cycle run by the CPU corresponds to 4 PPU cycles
+        self.mmu.run(cycles * 4);
+
+        Ok(cycles)
     }
 
-    /// Run one step the CPU, fetching/decoding/executing at the PC
-    pub fn step(&mut self) -> u64 {
+    /// Run one step the CPU, fetching/decoding/executing at the PC. An invalid opcode is returned
+    ///  as an error, with the PC left just past it.
+    pub fn step(&mut self) -> Result<u64, EmulatorError> {
+
+        // The CPU does nothing until a speed switch has settled, while the rest of the system runs
+        if self.speed_switch_delay > 0 {
+            self.speed_switch_delay -= 4;
+            return Ok(4);
+        }
 
         // The IME has a delay of one cycle, so when 're-enabled' there must be a delay
         //  before actually re-enabling it.
@@ -119,13 +180,13 @@ impl LR35902 {
                 };
 
                 self.call(interrupt_vector);
-                return 4;
+                return Ok(4);
             }
 
         // Resume execution with one-cycle delay if halted
         } else if self.status == HALTED {
             self.status = RUNNING;
-            return self.nop_0x00();
+            return Ok(self.nop_0x00());
         }
 
         // println!("program counter: {:#06X}", self.registers.pc);
@@ -139,7 +200,7 @@ impl LR35902 {
         //  Fibonacci sequence (starting at 3) across the B-L registers.
         if self.mooneye_testing && opcode == 0x40 {
             self.status = HALTED;
-            return 0;
+            return Ok(0);
         }
 
         self.call_instruction(opcode)        // Execute from standard table
@@ -228,8 +289,14 @@ impl LR35902 {
 
     pub fn stop(&mut self) {
         self.mmu.write(0xFF04, 0);  // A STOP clears the timer
-        // TODO - this should actually halt the timer until resumed (maybe?)
-        self.status = STOPPED;
+
+        // On a CGB with a speed switch prepared, STOP switches speed and carries on once the
+        //  clock has settled, rather than stopping
+        match self.mmu.switch_speed() {
+            true => self.speed_switch_delay = SPEED_SWITCH_CYCLES,
+            // TODO - this should actually halt the timer until resumed (maybe?)
+            false => self.status = STOPPED
+        }
     }
 
     pub fn halt(&mut self) {
@@ -456,7 +523,7 @@ impl LR35902 {
     /// SWAP - return the value with higher order bits swapped with lower order bits
     pub fn swap(&mut self, s: u8) -> u8 {
 
-        let result = (s << 4) | (s >> 4);
+        let result = s.rotate_right(4);
         match result == 0 {
             true => self.registers.set_zero(),
             false => self.registers.unset_zero(),
@@ -486,7 +553,7 @@ impl LR35902 {
     /// RRC - Rotate a number right, and copy the right-most bit shifted into the C register
     pub fn rrc(&mut self, v: u8) -> u8 {
 
-        let result = (v >> 1) | (v << 7);
+        let result = v.rotate_right(1);
 
         match result == 0 {
             true  => self.registers.set_zero(),
@@ -528,7 +595,7 @@ impl LR35902 {
 
     /// RLC - Rotate a number left, and copy the left-most bit shifted into the C register
     pub fn rlc(&mut self, v: u8) -> u8 {
-        let result = (v << 1) | (v >> 7);
+        let result = v.rotate_left(1);
 
         match result == 0 {
             true  => self.registers.set_zero(),
@@ -688,13 +755,6 @@ mod test {
     }
 
 
-    /* TODO - Enable when ready
-    #[test]
-    fn acceptance_oam_dma() {
-        mooneye_all(&format!("{}/{}", MOONEYE, "acceptance/oam_dma"));
-    }
-     */
-
     /* TODO
     #[test]
     fn acceptance_serial() {
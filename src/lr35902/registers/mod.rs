@@ -1,5 +1,8 @@
 use std::fmt;
+use crate::cartridge::Cartridge;
+use crate::cartridge::metadata::CgbSupport;
 use crate::lr35902::LR35902;
+use crate::model::Model;
 
 pub struct Registers {
     pub a: u8,
@@ -13,6 +16,7 @@ pub struct Registers {
     pub pc: u16,
     pub sp: u16,
 
+    #[allow(dead_code)]
     pub ime: bool,
 }
 
@@ -32,6 +36,74 @@ impl Registers {
         }
     }
 
+    /// The registers at power on, for a boot ROM to start from
+    pub fn power_on() -> Registers {
+        Registers {
+            a: 0x00, f: 0x00,
+            b: 0x00, c: 0x00,
+            d: 0x00, e: 0x00,
+            h: 0x00, l: 0x00,
+
+            pc: 0x0000,
+            sp: 0x0000,
+
+            ime: false,
+        }
+    }
+
+    /// The registers as a model's boot ROM leaves them on handing over to a cartridge, for
+    ///  starting without a boot ROM
+    pub fn post_boot(model: Model, cartridge: &Cartridge) -> Registers {
+
+        // The DMG and MGB boot ROMs leave H and C set unless the header checksum is 0
+        let flags = match cartridge.rom[0x014D] {
+            0 => 0x80,
+            _ => 0xB0
+        };
+
+        let (a, f, b, c, d, e, h, l) = match model {
+            Model::DMG0 => (0x01, 0x00, 0xFF, 0x13, 0x00, 0xC1, 0x84, 0x03),
+            Model::DMG  => (0x01, flags, 0x00, 0x13, 0x00, 0xD8, 0x01, 0x4D),
+            Model::MGB  => (0xFF, flags, 0x00, 0x13, 0x00, 0xD8, 0x01, 0x4D),
+            Model::SGB  => (0x01, 0x00, 0x00, 0x14, 0x00, 0x00, 0xC0, 0x60),
+
+            // The AGB runs the CGB boot ROM, whose check for AGB hardware ends in an INC B
+            Model::AGB => {
+                let cgb = Registers::post_boot(Model::CGB, cartridge);
+                let b = cgb.b.wrapping_add(1);
+                let f = ((b == 0) as u8 * 0x80) | ((b & 0x0F == 0) as u8 * 0x20);
+                (0x11, f, b, cgb.c, cgb.d, cgb.e, cgb.h, cgb.l)
+            },
+
+            Model::CGB => match cartridge.cgb_support() {
+                CgbSupport::Enhanced | CgbSupport::Only => (0x11, 0x80, 0x00, 0x00, 0xFF, 0x56, 0x00, 0x0D),
+
+                // For DMG games, the boot ROM leaves behind the title checksum it used to pick
+                //  a palette for Nintendo's own games
+                CgbSupport::None => {
+                    let b = match (cartridge.rom[0x014B], &cartridge.rom[0x0144..=0x0145]) {
+                        (0x01, _) | (0x33, b"01") => cartridge.rom[0x0134..=0x0143].iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte)),
+                        _ => 0x00
+                    };
+                    let (h, l) = match b {
+                        0x43 | 0x58 => (0x99, 0x1A),
+                        _ => (0x00, 0x7C)
+                    };
+                    (0x11, 0x80, b, 0x00, 0x00, 0x08, h, l)
+                }
+            }
+        };
+
+        Registers {
+            a, f, b, c, d, e, h, l,
+
+            pc: 0x0100,
+            sp: 0xFFFE,
+
+            ime: false,
+        }
+    }
+
     /*************************/
     /*     Register Pairs    */
     /*************************/
@@ -188,7 +260,10 @@ impl fmt::Debug for Registers {
 #[cfg(test)]
 mod tests {
 
+    use crate::cartridge::Cartridge;
     use crate::lr35902::registers::Registers;
+    use crate::model::Model;
+    use crate::testing::rom;
 
     #[test]
     fn get_initial() {
@@ -203,7 +278,34 @@ mod tests {
         assert_eq!(registers.l, 0x4D);
         assert_eq!(registers.pc, 0x0100);
         assert_eq!(registers.sp, 0xFFFE);
-        assert_eq!(registers.ime, false);
+        assert!(!registers.ime);
+    }
+
+    #[test]
+    fn post_boot() {
+        let game = Cartridge::parse(rom(0x00, 0x00, 0x00, &[])).unwrap();
+
+        let registers = Registers::post_boot(Model::DMG, &game);
+        assert_eq!(registers.get_af(), 0x01B0);
+        assert_eq!(registers.pc, 0x0100);
+
+        assert_eq!(Registers::post_boot(Model::DMG0, &game).get_bc(), 0xFF13);
+        assert_eq!(Registers::post_boot(Model::MGB, &game).a, 0xFF);
+        assert_eq!(Registers::post_boot(Model::SGB, &game).get_hl(), 0xC060);
+
+        // A DMG game on a CGB; the test ROM's title isn't one of Nintendo's
+        let registers = Registers::post_boot(Model::CGB, &game);
+        assert_eq!(registers.get_af(), 0x1180);
+        assert_eq!(registers.get_hl(), 0x007C);
+
+        let mut data = rom(0x00, 0x00, 0x00, &[]);
+        data[0x0143] = 0x80;
+        let registers = Registers::post_boot(Model::CGB, &Cartridge::parse(data.clone()).unwrap());
+        assert_eq!(registers.get_de(), 0xFF56);
+
+        let registers = Registers::post_boot(Model::AGB, &Cartridge::parse(data).unwrap());
+        assert_eq!(registers.get_af(), 0x1100);
+        assert_eq!(registers.get_bc(), 0x0100);
     }
 
     #[test]
@@ -1,19 +1,11 @@
-mod mmu;
-mod lr35902;
-mod cartridge;
-mod ppu;
-mod sound;
-mod timer;
-mod joypad;
-mod serial;
-
-mod traits;
-mod enums;
-mod testing;
-
 use std::{fs::read, env::args};
+use std::path::Path;
 use std::process::exit;
 
+use rustboy::{BootRom, Cartridge, GameBoy, Model};
+use rustboy::frontend::image;
+use rustboy::frontend::save::SaveFile;
+
 
 const DEFAULT_ROM: &str = "./roms/Tetris (World).gb";
 
@@ -21,32 +13,148 @@ fn main() {
 
     let cli_args: Vec<String> = args().collect();
 
-    let file = match cli_args.len() {
-        1 => {
+    // `rustboy info <rom>` prints the decoded header and exits without running anything
+    if cli_args.get(1).map(String::as_str) == Some("info") {
+        match cli_args.get(2) {
+            Some(file) => info(file),
+            None => fail("usage: rustboy info <rom>")
+        }
+    }
+
+    // Options, each taking a value, then the ROM
+    let mut boot_rom = None;
+    let mut camera = None;
+    let mut model = None;
+    let mut files = Vec::new();
+    let mut remaining = cli_args.iter().skip(1);
+    while let Some(arg) = remaining.next() {
+        match arg.as_str() {
+            "--boot-rom" => boot_rom = Some(remaining.next().unwrap_or_else(|| fail("--boot-rom needs a boot ROM file"))),
+            "--camera" => camera = Some(remaining.next().unwrap_or_else(|| fail("--camera needs an image file"))),
+            "--model" => model = match remaining.next().map(|name| Model::from_name(name)) {
+                Some(Some(model)) => Some(model),
+                _ => fail("--model needs one of dmg0, dmg, mgb, sgb, cgb or agb")
+            },
+            option if option.starts_with("--") => fail(format!("unknown option {}", option)),
+            file => files.push(file)
+        }
+    }
+
+    let file = match files.len() {
+        0 => {
             println!("supply the path of a ROM to load");
             println!("defaulting to {}", DEFAULT_ROM);
             DEFAULT_ROM
         },
-        2 => &cli_args[1],
+        1 => files[0],
         _ => {
             println!("ignoring additional args");
-            println!("using {}", cli_args[1]);
-            &cli_args[1]
+            println!("using {}", files[0]);
+            files[0]
         }
     };
 
-    let cartridge = match read(file) {
-        Ok(bytes) => cartridge::Cartridge::new(bytes),
-        Err(err) => panic!("{}", err)
-    };
+    let cartridge = load(file);
 
     println!("{:?}", cartridge);
 
-    let mut cpu = lr35902::LR35902::new(cartridge);
+    for warning in cartridge.warnings() {
+        println!("warning: {}", warning);
+    }
+
+    let boot_rom = boot_rom.map(|file| load_boot_rom(file));
+    let gameboy = match (model, boot_rom) {
+        (Some(model), boot_rom) => GameBoy::with_model(cartridge, model, boot_rom),
+        (None, Some(boot_rom)) => GameBoy::with_boot_rom(cartridge, boot_rom),
+        (None, None) => GameBoy::new(cartridge)
+    };
+    let mut gameboy = match gameboy {
+        Ok(gameboy) => gameboy,
+        Err(err) => fail(err)
+    };
+
+    if let Some(camera) = camera {
+        match image::load(Path::new(camera)) {
+            Ok(image) => gameboy.set_sensor_image(image),
+            Err(err) => fail(format!("could not read {}: {}", camera, err))
+        }
+    }
+
+    let mut save = SaveFile::for_rom(Path::new(file));
+    if let Err(err) = save.load(&mut gameboy) {
+        fail(format!("could not read {}: {}", save.path().display(), err))
+    }
+
+    println!("running as {:?}", gameboy.model());
+    println!("{:?}", gameboy.cpu());
+
+    #[cfg(feature = "sdl")]
+    if let Err(err) = rustboy::frontend::sdl::run(&mut gameboy, &mut save) {
+        fail(err)
+    }
+
+    #[cfg(not(feature = "sdl"))]
+    {
+        println!("built without the sdl feature; running headless");
+        if let Err(err) = gameboy.cpu_mut().run() {
+            fail(err)
+        }
+        if let Err(err) = save.flush(&gameboy) {
+            fail(format!("could not write {}: {}", save.path().display(), err))
+        }
+    }
+
+    exit(0);
+}
+
+
+/// Read and parse a ROM, exiting on failure
+fn load(file: &str) -> Cartridge {
+
+    let bytes = match read(file) {
+        Ok(bytes) => bytes,
+        Err(err) => fail(format!("could not read {}: {}", file, err))
+    };
+
+    match Cartridge::parse(bytes) {
+        Ok(cartridge) => cartridge,
+        Err(err) => fail(err)
+    }
+}
+
+
+/// Read a boot ROM image, exiting on failure
+fn load_boot_rom(file: &str) -> BootRom {
+
+    let bytes = match read(file) {
+        Ok(bytes) => bytes,
+        Err(err) => fail(format!("could not read {}: {}", file, err))
+    };
+
+    match BootRom::new(bytes) {
+        Ok(boot_rom) => boot_rom,
+        Err(err) => fail(err)
+    }
+}
+
+
+/// Print the decoded header of a ROM, along with anything found wrong with it
+fn info(file: &str) -> ! {
 
-    println!("{:?}", cpu);
+    let cartridge = load(file);
 
-    cpu.run();
+    println!("{:?}", cartridge);
+
+    for warning in cartridge.warnings() {
+        println!("warning: {}", warning);
+    }
 
     exit(0);
 }
+
+
+/// Report an error and exit with a failure status
+fn fail(err: impl std::fmt::Display) -> ! {
+    eprintln!("error: {}", err);
+    exit(1);
+}
@@ -0,0 +1,42 @@
+use crate::error::EmulatorError;
+
+const DMG_SIZE: usize = 0x0100;
+const CGB_SIZE: usize = 0x0900;
+
+
+/// A boot ROM image, mapped over the start of the cartridge at power on until the boot ROM
+/// unmaps itself by writing to 0xFF50. DMG0, DMG, MGB and SGB images cover 0x0000 - 0x00FF; CGB
+/// images also cover 0x0200 - 0x08FF, leaving the cartridge header visible in between.
+pub struct BootRom {
+    data: Vec<u8>
+}
+
+
+impl BootRom {
+
+    /// Check a boot ROM image is one of the two sizes there are
+    pub fn new(data: Vec<u8>) -> Result<BootRom, EmulatorError> {
+        match data.len() {
+            DMG_SIZE | CGB_SIZE => Ok(BootRom { data }),
+            size => Err(EmulatorError::InvalidBootRom { size })
+        }
+    }
+
+    /// Whether this is a CGB boot ROM, which needs CGB hardware to run
+    pub fn is_cgb(&self) -> bool {
+        self.data.len() == CGB_SIZE
+    }
+
+    /// Whether the boot ROM, rather than the cartridge, is read at an address while it's mapped
+    pub fn maps(&self, address: u16) -> bool {
+        match address {
+            0x0000 ..= 0x00FF => true,
+            0x0200 ..= 0x08FF => self.is_cgb(),
+            _ => false
+        }
+    }
+
+    pub fn read(&self, address: u16) -> u8 {
+        self.data[address as usize]
+    }
+}
@@ -0,0 +1,128 @@
+/// Bytes copied by a VRAM DMA at a time
+pub const BLOCK_SIZE: u16 = 0x10;
+
+/// Clock cycles the CPU is held for while a block is copied, at normal speed
+pub const BLOCK_CYCLES: u64 = 32;
+
+
+/// The CGB's VRAM DMA (0xFF51 - 0xFF55), which copies blocks of 16 bytes from ROM or RAM into
+/// VRAM: either all at once (general-purpose DMA), or one block in each HBlank (HBlank DMA). The
+/// MMU does the copying, as it can see both ends; this keeps track of where the copy is up to.
+pub struct Hdma {
+    source: u16,        // 0xFF51 - 0xFF52 : HDMA1 / HDMA2 : Source, a multiple of 16
+    destination: u16,   // 0xFF53 - 0xFF54 : HDMA3 / HDMA4 : Offset into VRAM, a multiple of 16
+    length: u8,         // 0xFF55 : HDMA5 : Blocks left to copy, minus one
+    hblank: bool,       // An HBlank DMA is underway
+    general: bool       // A general-purpose DMA has been started, and is waiting to be copied
+}
+
+
+impl Hdma {
+
+    pub fn new() -> Hdma {
+        Hdma {
+            source: 0,
+            destination: 0,
+            length: 0x7F,
+            hblank: false,
+            general: false
+        }
+    }
+
+    /// Only HDMA5 can be read: the blocks left to copy, with bit 7 clear while an HBlank DMA is
+    ///  underway; 0xFF once a transfer has finished
+    pub fn read(&self, address: u16) -> u8 {
+        match address {
+            0xFF55 => (!self.hblank as u8) << 7 | self.length,
+            _ => 0xFF
+        }
+    }
+
+    pub fn write(&mut self, address: u16, value: u8) {
+        match address {
+            0xFF51 => self.source = (value as u16) << 8 | (self.source & 0x00FF),
+            0xFF52 => self.source = (self.source & 0xFF00) | (value & 0xF0) as u16,
+            0xFF53 => self.destination = ((value & 0x1F) as u16) << 8 | (self.destination & 0x00FF),
+            0xFF54 => self.destination = (self.destination & 0xFF00) | (value & 0xF0) as u16,
+
+            // Writing with bit 7 clear during an HBlank DMA cancels it, leaving the blocks left
+            0xFF55 if self.hblank && value & 0x80 == 0 => self.hblank = false,
+            0xFF55 => {
+                self.length = value & 0x7F;
+                match value & 0x80 != 0 {
+                    true => self.hblank = true,
+                    false => self.general = true
+                }
+            },
+
+            _ => ()
+        }
+    }
+
+    /// Take a general-purpose DMA that's been started, returning how many blocks to copy
+    pub fn take_general(&mut self) -> Option<u16> {
+        match std::mem::replace(&mut self.general, false) {
+            true => Some(self.length as u16 + 1),
+            false => None
+        }
+    }
+
+    /// Whether an HBlank DMA is waiting for the next HBlank to copy a block
+    pub fn hblank_pending(&self) -> bool {
+        self.hblank
+    }
+
+    /// The source and VRAM destination of the next block to copy, moving the transfer on past
+    ///  it; the destination wraps around within VRAM
+    pub fn next_block(&mut self) -> (u16, u16) {
+        let block = (self.source, 0x8000 | self.destination);
+
+        self.source = self.source.wrapping_add(BLOCK_SIZE);
+        self.destination = (self.destination + BLOCK_SIZE) & 0x1FF0;
+
+        // The length wraps around to 0x7F as the last block is copied, ending the transfer
+        self.length = self.length.wrapping_sub(1) & 0x7F;
+        if self.length == 0x7F {
+            self.hblank = false;
+        }
+
+        block
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+
+    use crate::mmu::hdma::Hdma;
+
+    #[test]
+    fn hblank_transfer() {
+        let mut hdma = Hdma::new();
+        assert_eq!(hdma.read(0xFF55), 0xFF);
+
+        hdma.write(0xFF51, 0xC1);
+        hdma.write(0xFF52, 0x2F);
+        hdma.write(0xFF53, 0xFF);
+        hdma.write(0xFF54, 0xF0);
+        hdma.write(0xFF55, 0x82);
+        assert_eq!(hdma.take_general(), None);
+        assert!(hdma.hblank_pending());
+        assert_eq!(hdma.read(0xFF55), 0x02);
+
+        assert_eq!(hdma.next_block(), (0xC120, 0x9FF0));
+        assert_eq!(hdma.next_block(), (0xC130, 0x8000));
+        assert_eq!(hdma.read(0xFF55), 0x00);
+
+        // Cancelled with one block left
+        hdma.write(0xFF55, 0x00);
+        assert!(!hdma.hblank_pending());
+        assert_eq!(hdma.read(0xFF55), 0x80);
+
+        hdma.write(0xFF55, 0x81);
+        hdma.next_block();
+        hdma.next_block();
+        assert!(!hdma.hblank_pending());
+        assert_eq!(hdma.read(0xFF55), 0xFF);
+    }
+}
@@ -0,0 +1,377 @@
+use crate::cartridge::Cartridge;
+use crate::error::EmulatorError;
+use super::{MBC, create_ram, load_ram, ROM_BANK_SIZE, RAM_BANK_SIZE};
+
+/// Size of the M64282FP sensor's image, as the camera ROM sees it
+pub const SENSOR_WIDTH: usize = 128;
+pub const SENSOR_HEIGHT: usize = 112;
+
+// The camera's registers, mirrored every 0x80 bytes of 0xA000 - 0xBFFF
+const REGISTER_COUNT: usize = 0x36;
+const CAPTURE: usize = 0x00;            // Bit 0 starts a capture, and reads set until it's done
+const EDGE_AND_GAIN: usize = 0x01;      // Bit 7 N, bits 5-6 VH, bits 0-4 gain
+const EXPOSURE_HIGH: usize = 0x02;
+const EXPOSURE_LOW: usize = 0x03;
+const EDGE_RATIO_AND_INVERT: usize = 0x04;  // Bits 4-6 edge enhancement ratio, bit 3 invert
+const DITHER_MATRIX: usize = 0x06;      // 4x4 pixels, each with 3 thresholds
+
+// Where a finished capture is written, in RAM bank 0, as 16 x 14 tiles
+const IMAGE_ADDRESS: usize = 0x0100;
+
+const EDGE_RATIOS: [f32; 8] = [0.50, 0.75, 1.00, 1.25, 2.00, 3.00, 4.00, 5.00];
+
+
+/// A greyscale picture for the sensor to look at: SENSOR_WIDTH x SENSOR_HEIGHT bytes, row-major,
+/// where 0 is black and 255 is white
+#[derive(Clone, Debug, PartialEq)]
+pub struct SensorImage {
+    pixels: Vec<u8>
+}
+
+
+impl SensorImage {
+
+    /// Fit a greyscale picture of any size to the sensor, stretching it as needed
+    pub fn from_luma(width: usize, height: usize, pixels: &[u8]) -> SensorImage {
+        assert_eq!(pixels.len(), width * height);
+
+        let pixels = (0..SENSOR_HEIGHT)
+            .flat_map(|y| (0..SENSOR_WIDTH).map(move |x| (x, y)))
+            .map(|(x, y)| pixels[(y * height / SENSOR_HEIGHT) * width + x * width / SENSOR_WIDTH])
+            .collect();
+
+        SensorImage { pixels }
+    }
+
+    /// Something to look at without an image file: a grey ramp across the top half, and a
+    ///  checkerboard across the bottom half
+    pub fn test_pattern() -> SensorImage {
+        let pixels = (0..SENSOR_HEIGHT)
+            .flat_map(|y| (0..SENSOR_WIDTH).map(move |x| (x, y)))
+            .map(|(x, y)| match y < SENSOR_HEIGHT / 2 {
+                true => (x * 255 / (SENSOR_WIDTH - 1)) as u8,
+                false => match (x / 16 + y / 16) % 2 {
+                    0 => 0x20,
+                    _ => 0xE0
+                }
+            })
+            .collect();
+
+        SensorImage { pixels }
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> u8 {
+        self.pixels[y * SENSOR_WIDTH + x]
+    }
+}
+
+
+/// The Pocket Camera (Game Boy Camera): MBC5-style banking over 128 KiB of RAM, with bit 4 of
+/// the RAM bank swapping the M64282FP sensor's registers in for RAM. Writing bit 0 of register 0
+/// takes a picture, which some time later is processed the way the sensor and the cartridge's
+/// ADC do - gain and exposure, edge enhancement, then thresholds from the dither matrix - and
+/// written into RAM bank 0 as 2bpp tiles.
+///
+/// The "sensor" sees a fixed SensorImage rather than the world. The sensor's reference voltages
+/// (register 4 bits 0-2 and register 5) aren't modelled, and edge enhancement is only applied in
+/// the two-dimensional mode the camera ROM uses (N and both VH bits set).
+pub struct PocketCamera {
+    cartridge: Cartridge,
+    rom_bank: u8,
+
+    ram: Vec<u8>,
+    ram_bank: u8,
+    ram_enabled: bool,
+
+    // 0xA000 - 0xBFFF is the camera's registers rather than RAM
+    registers_mapped: bool,
+    registers: [u8; REGISTER_COUNT],
+
+    capture_cycles: u64,    // Cycles left until the capture in progress finishes; 0 when idle
+    image: SensorImage
+}
+
+
+impl PocketCamera {
+    pub fn new(cartridge: Cartridge) -> Result<PocketCamera, EmulatorError> {
+
+        let ram = create_ram(cartridge.rom[0x149])?;
+
+        Ok(PocketCamera {
+            cartridge,
+            rom_bank: 1,
+
+            ram,
+            ram_bank: 0,
+            ram_enabled: false,
+
+            registers_mapped: false,
+            registers: [0; REGISTER_COUNT],
+
+            capture_cycles: 0,
+            image: SensorImage::test_pattern()
+        })
+    }
+
+    fn ram_address(&self, address: u16) -> usize {
+        ((self.ram_bank as usize * RAM_BANK_SIZE) | (address as usize & (RAM_BANK_SIZE - 1))) % self.ram.len()
+    }
+
+    fn exposure(&self) -> u16 {
+        (self.registers[EXPOSURE_HIGH] as u16) << 8 | self.registers[EXPOSURE_LOW] as u16
+    }
+
+    /// How long a capture takes, in clock cycles: a fixed readout time, plus the exposure
+    ///  (in units of 16 M-cycles), plus a little more when N is clear
+    fn capture_time(&self) -> u64 {
+        let n = match self.registers[EDGE_AND_GAIN] & 0x80 {
+            0 => 512,
+            _ => 0
+        };
+        4 * (32446 + n + 16 * self.exposure() as u64)
+    }
+
+    /// What the sensor reads for a pixel, amplified and scaled by the exposure time; 255 is as
+    ///  bright as an unamplified white pixel exposed for 0x1000 units. Pixels beyond the edges
+    ///  repeat the nearest one, for edge enhancement.
+    fn sensor_value(&self, x: isize, y: isize) -> f32 {
+
+        let x = x.clamp(0, SENSOR_WIDTH as isize - 1) as usize;
+        let y = y.clamp(0, SENSOR_HEIGHT as isize - 1) as usize;
+
+        // Gain goes up in steps of 1.5 dB
+        let gain = 10f32.powf((self.registers[EDGE_AND_GAIN] & 0x1F) as f32 * 1.5 / 20.0);
+
+        self.image.pixel(x, y) as f32 * gain * self.exposure() as f32 / 0x1000 as f32
+    }
+
+    /// The 2-bit colour of a pixel, after edge enhancement, inversion and dithering
+    fn processed_colour(&self, x: usize, y: usize) -> u8 {
+
+        let (sx, sy) = (x as isize, y as isize);
+        let mut value = self.sensor_value(sx, sy);
+
+        if self.registers[EDGE_AND_GAIN] & 0xE0 == 0xE0 {
+            let ratio = EDGE_RATIOS[(self.registers[EDGE_RATIO_AND_INVERT] >> 4) as usize & 0x07];
+            let neighbours = self.sensor_value(sx - 1, sy) + self.sensor_value(sx + 1, sy)
+                + self.sensor_value(sx, sy - 1) + self.sensor_value(sx, sy + 1);
+            value += (value * 4.0 - neighbours) * ratio;
+        }
+
+        let value = value.clamp(0.0, 255.0) as u8;
+        let value = match self.registers[EDGE_RATIO_AND_INVERT] & 0x08 {
+            0 => value,
+            _ => 255 - value
+        };
+
+        // Each position in the 4x4 matrix has its own three thresholds, darkest first
+        let thresholds = DITHER_MATRIX + ((x & 3) + (y & 3) * 4) * 3;
+        match value {
+            v if v < self.registers[thresholds] => 3,
+            v if v < self.registers[thresholds + 1] => 2,
+            v if v < self.registers[thresholds + 2] => 1,
+            _ => 0
+        }
+    }
+
+    /// Take the picture, writing it into RAM bank 0 as 2bpp tiles
+    fn capture(&mut self) {
+
+        // A cartridge whose header claims too little RAM has nowhere to put the picture
+        if self.ram.len() < IMAGE_ADDRESS + SENSOR_WIDTH * SENSOR_HEIGHT / 4 {
+            return;
+        }
+
+        for y in 0..SENSOR_HEIGHT {
+            for x in 0..SENSOR_WIDTH {
+
+                let colour = self.processed_colour(x, y);
+                let tile = (y / 8) * (SENSOR_WIDTH / 8) + x / 8;
+                let address = IMAGE_ADDRESS + tile * 16 + (y % 8) * 2;
+                let bit = 0x80 >> (x % 8);
+
+                for (plane, mask) in [0x01, 0x02].iter().enumerate() {
+                    match colour & mask {
+                        0 => self.ram[address + plane] &= !bit,
+                        _ => self.ram[address + plane] |= bit
+                    }
+                }
+            }
+        }
+    }
+}
+
+
+impl MBC for PocketCamera {
+
+    fn read(&mut self, address: u16) -> u8 {
+        match address {
+
+            // ROM Read - Bank 0
+            0x0000..=0x3FFF => self.cartridge.rom[address as usize],
+
+            // ROM Read - Banked
+            0x4000..=0x7FFF => {
+                let address = (ROM_BANK_SIZE * self.rom_bank as usize) | (address as usize & (ROM_BANK_SIZE - 1));
+                self.cartridge.rom[address % self.cartridge.rom.len()]
+            },
+
+            // Camera Register Read - only register 0 can be read back, with bit 0 set while a
+            //  capture is in progress
+            0xA000..=0xBFFF if self.registers_mapped => match address as usize & 0x7F {
+                CAPTURE => (self.registers[CAPTURE] & 0x06) | (self.capture_cycles > 0) as u8,
+                _ => 0x00
+            },
+
+            // RAM Read - unlike a write, this works whether or not RAM is enabled
+            0xA000..=0xBFFF if !self.ram.is_empty() => self.ram[self.ram_address(address)],
+
+            _ => 0xFF
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        match address {
+
+            // RAM Enable
+            0x0000..=0x1FFF => self.ram_enabled = value & 0x0F == 0x0A,
+
+            // ROM Bank Number - 6 bits, where bank 0 can be selected
+            0x2000..=0x3FFF => self.rom_bank = value & 0x3F,
+
+            // RAM Bank Number / Camera Register Select
+            0x4000..=0x5FFF => {
+                self.registers_mapped = value & 0x10 != 0;
+                self.ram_bank = value & 0x0F;
+            },
+
+            // Camera Register Write
+            0xA000..=0xBFFF if self.registers_mapped => match address as usize & 0x7F {
+                CAPTURE => {
+                    self.registers[CAPTURE] = value & 0x07;
+                    if value & 0x01 != 0 && self.capture_cycles == 0 {
+                        self.capture_cycles = self.capture_time();
+                    }
+                },
+                register if register < REGISTER_COUNT => self.registers[register] = value,
+                _ => ()
+            },
+
+            // RAM Write
+            0xA000..=0xBFFF if self.ram_enabled && !self.ram.is_empty() => {
+                let address = self.ram_address(address);
+                self.ram[address] = value;
+            },
+
+            _ => ()
+        }
+    }
+
+    fn run(&mut self, clock_cycles: u64) {
+        if self.capture_cycles == 0 {
+            return;
+        }
+
+        self.capture_cycles = self.capture_cycles.saturating_sub(clock_cycles);
+        if self.capture_cycles == 0 {
+            self.registers[CAPTURE] &= !0x01;
+            self.capture();
+        }
+    }
+
+    fn set_sensor_image(&mut self, image: SensorImage) {
+        self.image = image;
+    }
+
+    fn save_data(&self) -> Option<Vec<u8>> {
+        match self.cartridge.kind().features.battery && !self.ram.is_empty() {
+            true  => Some(self.ram.clone()),
+            false => None
+        }
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        if self.cartridge.kind().features.battery {
+            load_ram(&mut self.ram, data);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+
+    use crate::cartridge::Cartridge;
+    use crate::testing::rom;
+    use crate::traits::MBC;
+    use super::{PocketCamera, SensorImage, SENSOR_WIDTH, SENSOR_HEIGHT};
+
+    fn camera() -> PocketCamera {
+        PocketCamera::new(Cartridge::parse(rom(0xFC, 0x00, 0x04, &[])).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn registers_replace_ram() {
+        let mut mbc = camera();
+        mbc.write(0x0000, 0x0A);
+        mbc.write(0xA000, 0x42);
+
+        mbc.write(0x4000, 0x10);
+        assert_eq!(mbc.read(0xA000), 0x00);
+        mbc.write(0xA001, 0x42);
+        assert_eq!(mbc.read(0xA001), 0x00);
+
+        mbc.write(0x4000, 0x00);
+        assert_eq!(mbc.read(0xA000), 0x42);
+
+        // RAM can be read, but not written, while disabled
+        mbc.write(0x0000, 0x00);
+        mbc.write(0xA000, 0x24);
+        assert_eq!(mbc.read(0xA000), 0x42);
+    }
+
+    #[test]
+    fn capture() {
+        let mut mbc = camera();
+
+        // Black on the left half, white on the right
+        let pixels: Vec<u8> = (0..SENSOR_WIDTH * SENSOR_HEIGHT)
+            .map(|i| match i % SENSOR_WIDTH < SENSOR_WIDTH / 2 { true => 0x00, false => 0xFF })
+            .collect();
+        mbc.set_sensor_image(SensorImage::from_luma(SENSOR_WIDTH, SENSOR_HEIGHT, &pixels));
+
+        // No gain, an exposure of 0x1000 and the same thresholds everywhere
+        mbc.write(0x4000, 0x10);
+        mbc.write(0xA001, 0x00);
+        mbc.write(0xA002, 0x10);
+        mbc.write(0xA003, 0x00);
+        for pixel in 0..16 {
+            mbc.write(0xA006 + pixel * 3, 0x40);
+            mbc.write(0xA007 + pixel * 3, 0x80);
+            mbc.write(0xA008 + pixel * 3, 0xC0);
+        }
+
+        mbc.write(0xA000, 0x01);
+        assert_eq!(mbc.read(0xA000), 0x01);
+        mbc.run(4 * (32446 + 512 + 16 * 0x1000) - 1);
+        assert_eq!(mbc.read(0xA000), 0x01);
+        mbc.run(1);
+        assert_eq!(mbc.read(0xA000), 0x00);
+
+        // The first tile is black and the last white
+        mbc.write(0x4000, 0x00);
+        assert_eq!(mbc.read(0xA100), 0xFF);
+        assert_eq!(mbc.read(0xA101), 0xFF);
+        assert_eq!(mbc.read(0xAEFE), 0x00);
+        assert_eq!(mbc.read(0xAEFF), 0x00);
+
+        // Inverted, the other way around
+        mbc.write(0x4000, 0x10);
+        mbc.write(0xA004, 0x08);
+        mbc.write(0xA000, 0x01);
+        mbc.run(u64::MAX);
+        mbc.write(0x4000, 0x00);
+        assert_eq!(mbc.read(0xA100), 0x00);
+        assert_eq!(mbc.read(0xAEFF), 0xFF);
+    }
+}
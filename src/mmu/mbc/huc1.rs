@@ -0,0 +1,137 @@
+use std::cmp::max;
+use crate::cartridge::Cartridge;
+use crate::error::EmulatorError;
+use super::{MBC, create_ram, load_ram, ROM_BANK_SIZE, RAM_BANK_SIZE};
+
+
+/// Hudson's HuC1: MBC1-style banking without the banking modes, and an infrared LED and receiver
+/// that can be swapped in for RAM at 0xA000 - 0xBFFF
+pub struct HuC1 {
+    cartridge: Cartridge,
+    rom_bank: u8,
+
+    ram: Vec<u8>,
+    ram_bank: u8,
+
+    // 0xA000 - 0xBFFF is the IR port rather than RAM
+    ir_mode: bool,
+    ir_led: bool
+}
+
+
+impl HuC1 {
+    pub fn new(cartridge: Cartridge) -> Result<HuC1, EmulatorError> {
+
+        let ram = create_ram(cartridge.rom[0x149])?;
+
+        Ok(HuC1 {
+            cartridge,
+            rom_bank: 1,
+
+            ram,
+            ram_bank: 0,
+
+            ir_mode: false,
+            ir_led: false
+        })
+    }
+
+    /// Whether the infrared LED is lit
+    pub fn ir_led(&self) -> bool {
+        self.ir_led
+    }
+
+    fn ram_address(&self, address: u16) -> usize {
+        ((self.ram_bank as usize * RAM_BANK_SIZE) | (address as usize & (RAM_BANK_SIZE - 1))) % self.ram.len()
+    }
+}
+
+
+impl MBC for HuC1 {
+
+    fn read(&mut self, address: u16) -> u8 {
+        match address {
+
+            // ROM Read - Bank 0
+            0x0000..=0x3FFF => self.cartridge.rom[address as usize],
+
+            // ROM Read - Banked
+            0x4000..=0x7FFF => {
+                let address = (ROM_BANK_SIZE * self.rom_bank as usize) | (address as usize & (ROM_BANK_SIZE - 1));
+                self.cartridge.rom[address % self.cartridge.rom.len()]
+            },
+
+            // IR Read - bit 0 is set while light is received; there's never another GameBoy
+            //  at the other end, so it stays dark
+            0xA000..=0xBFFF if self.ir_mode => 0xC0,
+
+            // RAM Read - there's no enable; RAM is always accessible outside of IR mode
+            0xA000..=0xBFFF if !self.ram.is_empty() => self.ram[self.ram_address(address)],
+
+            _ => 0xFF
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        match address {
+
+            // IR / RAM Select
+            0x0000..=0x1FFF => self.ir_mode = value & 0x0F == 0x0E,
+
+            // ROM Bank Number - 6 bits
+            0x2000..=0x3FFF => self.rom_bank = max(1, value & 0x3F),
+
+            // RAM Bank Number
+            0x4000..=0x5FFF => self.ram_bank = value & 0x03,
+
+            // IR Write - bit 0 switches the LED
+            0xA000..=0xBFFF if self.ir_mode => self.ir_led = value & 0x01 != 0,
+
+            // RAM Write
+            0xA000..=0xBFFF if !self.ram.is_empty() => {
+                let address = self.ram_address(address);
+                self.ram[address] = value;
+            },
+
+            _ => ()
+        }
+    }
+
+    fn save_data(&self) -> Option<Vec<u8>> {
+        match self.cartridge.kind().features.battery && !self.ram.is_empty() {
+            true  => Some(self.ram.clone()),
+            false => None
+        }
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        if self.cartridge.kind().features.battery {
+            load_ram(&mut self.ram, data);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+
+    use crate::cartridge::Cartridge;
+    use crate::testing::rom;
+    use crate::traits::MBC;
+    use super::HuC1;
+
+    #[test]
+    fn ir_replaces_ram() {
+        let mut mbc = HuC1::new(Cartridge::parse(rom(0xFF, 0x00, 0x03, &[])).unwrap()).unwrap();
+        mbc.write(0x4000, 0x01);
+        mbc.write(0xA000, 0x42);
+        assert_eq!(mbc.read(0xA000), 0x42);
+
+        mbc.write(0x0000, 0x0E);
+        assert_eq!(mbc.read(0xA000), 0xC0);
+        mbc.write(0xA000, 0x01);
+
+        mbc.write(0x0000, 0x00);
+        assert_eq!(mbc.read(0xA000), 0x42);
+    }
+}
@@ -0,0 +1,322 @@
+use std::cmp::max;
+use std::convert::TryInto;
+
+use crate::cartridge::Cartridge;
+use crate::error::EmulatorError;
+use super::{MBC, create_ram, load_ram, ROM_BANK_SIZE, RAM_BANK_SIZE};
+use super::rtc::{ClockSource, Timebase};
+
+const MINUTES_PER_DAY: u16 = 24 * 60;
+
+/// Size of the clock state appended to a save, in the layout SameBoy uses: the UNIX time of the
+///  save as 64 bits, then the minute of the day, the day count, the alarm's minute and day as 16
+///  bits each, and whether the alarm is enabled as a byte; all little-endian
+pub const FOOTER_SIZE: usize = 17;
+
+
+/// Hudson's HuC3: HuC1-style banking, with 0x0000 - 0x1FFF choosing what appears at
+/// 0xA000 - 0xBFFF - RAM, the clock's command and response registers, or the IR port. The clock
+/// counts minutes of the day and days, and is driven through a nibble-wide command protocol.
+pub struct HuC3 {
+    cartridge: Cartridge,
+    rom_bank: u8,
+
+    ram: Vec<u8>,
+    ram_bank: u8,
+
+    // What 0xA000 - 0xBFFF maps: 0x00 read-only RAM, 0x0A RAM, 0x0B clock command, 0x0C clock
+    //  response, 0x0D clock status, 0x0E IR
+    mode: u8,
+
+    // Clock registers, addressed a nibble at a time through access_index
+    minutes: u16,           // Minute of the day, 0-1439
+    days: u16,              // 12-bit day counter
+    alarm_minutes: u16,
+    alarm_days: u16,
+    alarm_enabled: bool,
+
+    access_index: u8,       // Which nibble the next clock command reads or writes
+    access_flags: u8,       // Argument of the last extended (0x6) command
+    response: u8,           // Result of the last read command
+
+    seconds: u8,            // Seconds towards the next minute
+    timebase: Timebase,
+
+    ir_led: bool
+}
+
+
+impl HuC3 {
+    pub fn new(cartridge: Cartridge) -> Result<HuC3, EmulatorError> {
+
+        let ram = create_ram(cartridge.rom[0x149])?;
+
+        Ok(HuC3 {
+            cartridge,
+            rom_bank: 1,
+
+            ram,
+            ram_bank: 0,
+
+            mode: 0,
+
+            minutes: 0,
+            days: 0,
+            alarm_minutes: 0,
+            alarm_days: 0,
+            alarm_enabled: false,
+
+            access_index: 0,
+            access_flags: 0,
+            response: 0,
+
+            seconds: 0,
+            timebase: Timebase::new(),
+
+            ir_led: false
+        })
+    }
+
+    /// Whether the infrared LED is lit
+    pub fn ir_led(&self) -> bool {
+        self.ir_led
+    }
+
+    fn ram_address(&self, address: u16) -> usize {
+        ((self.ram_bank as usize * RAM_BANK_SIZE) | (address as usize & (RAM_BANK_SIZE - 1))) % self.ram.len()
+    }
+
+    /// Move the clock forward; the HuC3 has no way to stop it
+    fn advance(&mut self, seconds: u64) {
+        let total_minutes = (self.seconds as u64 + seconds) / 60 + self.minutes as u64;
+        let days = total_minutes / MINUTES_PER_DAY as u64;
+
+        self.seconds = ((self.seconds as u64 + seconds) % 60) as u8;
+        self.minutes = (total_minutes % MINUTES_PER_DAY as u64) as u16;
+        self.days = ((self.days as u64 + days) & 0x0FFF) as u16;
+    }
+
+    /// Bring a host-driven clock up to date with the host's time
+    fn sync(&mut self) {
+        let elapsed = self.timebase.sync();
+        self.advance(elapsed);
+    }
+
+    /// Handle a command written in mode 0x0B: the command in the upper nibble, its argument in the
+    ///  lower
+    fn command(&mut self, value: u8) {
+
+        let argument = value & 0x0F;
+        let index = self.access_index;
+
+        match value >> 4 {
+
+            // Read the addressed nibble, and move on to the next
+            0x1 => {
+                self.sync();
+                self.response = match index {
+                    0x00..=0x02 => (self.minutes >> (index * 4)) as u8 & 0x0F,
+                    0x03..=0x06 => (self.days >> ((index - 3) * 4)) as u8 & 0x0F,
+                    _ => self.response
+                };
+                self.access_index = index.wrapping_add(1);
+            },
+
+            // Write the addressed nibble; 0x3 also moves on to the next
+            0x2 | 0x3 => {
+                self.sync();
+
+                let set = |register: u16, shift: u8| (register & !(0x0F << shift)) | (argument as u16) << shift;
+                match index {
+                    0x00..=0x02 => self.minutes = set(self.minutes, index * 4),
+                    0x03..=0x06 => self.days = set(self.days, (index - 3) * 4) & 0x0FFF,
+                    0x58..=0x5A => self.alarm_minutes = set(self.alarm_minutes, (index - 0x58) * 4),
+                    0x5B..=0x5E => self.alarm_days = set(self.alarm_days, (index - 0x5B) * 4),
+                    0x5F => self.alarm_enabled = argument & 0x01 != 0,
+                    _ => ()
+                }
+
+                if value >> 4 == 0x3 {
+                    self.access_index = index.wrapping_add(1);
+                }
+            },
+
+            // Set the low / high nibble of the access index
+            0x4 => self.access_index = (index & 0xF0) | argument,
+            0x5 => self.access_index = (index & 0x0F) | argument << 4,
+
+            // Extended command
+            0x6 => self.access_flags = argument,
+
+            _ => ()
+        }
+    }
+}
+
+
+impl MBC for HuC3 {
+
+    fn read(&mut self, address: u16) -> u8 {
+        match address {
+
+            // ROM Read - Bank 0
+            0x0000..=0x3FFF => self.cartridge.rom[address as usize],
+
+            // ROM Read - Banked
+            0x4000..=0x7FFF => {
+                let address = (ROM_BANK_SIZE * self.rom_bank as usize) | (address as usize & (ROM_BANK_SIZE - 1));
+                self.cartridge.rom[address % self.cartridge.rom.len()]
+            },
+
+            0xA000..=0xBFFF => match self.mode {
+
+                // RAM Read
+                0x00 | 0x0A if !self.ram.is_empty() => self.ram[self.ram_address(address)],
+                0x00 | 0x0A => 0xFF,
+
+                // Clock Response - extended command 2 asks whether the clock is working
+                0x0C if self.access_flags == 0x02 => 0x01,
+                0x0C => self.response,
+
+                // Clock Status - always ready
+                0x0D => 0x01,
+
+                // IR Read - no other device is ever in range, so nothing is received
+                0x0E => 0xC0,
+
+                // Anything else reads 1, as on SameBoy, which games are known to run against
+                _ => 0x01
+            },
+
+            _ => 0xFF
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        match address {
+
+            // Mode Select
+            0x0000..=0x1FFF => self.mode = value & 0x0F,
+
+            // ROM Bank Number - 7 bits
+            0x2000..=0x3FFF => self.rom_bank = max(1, value & 0x7F),
+
+            // RAM Bank Number
+            0x4000..=0x5FFF => self.ram_bank = value & 0x03,
+
+            0xA000..=0xBFFF => match self.mode {
+
+                // RAM Write
+                0x0A if !self.ram.is_empty() => {
+                    let address = self.ram_address(address);
+                    self.ram[address] = value;
+                },
+
+                // Clock Command
+                0x0B => self.command(value),
+
+                // IR Write - bit 0 switches the LED
+                0x0E => self.ir_led = value & 0x01 != 0,
+
+                _ => ()
+            },
+
+            _ => ()
+        }
+    }
+
+    fn run(&mut self, clock_cycles: u64) {
+        let seconds = self.timebase.run(clock_cycles);
+        self.advance(seconds);
+    }
+
+    fn set_clock_source(&mut self, source: ClockSource) {
+        self.sync();
+        self.timebase.set_source(source);
+    }
+
+    fn save_data(&self) -> Option<Vec<u8>> {
+        let mut data = self.ram.clone();
+        data.extend_from_slice(&self.timebase.timestamp().to_le_bytes());
+        for register in [self.minutes, self.days, self.alarm_minutes, self.alarm_days].iter() {
+            data.extend_from_slice(&register.to_le_bytes());
+        }
+        data.push(self.alarm_enabled as u8);
+
+        Some(data)
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+
+        load_ram(&mut self.ram, data);
+
+        // The clock, then fast-forwarded by however long it's been since the save was made
+        let footer = match data.get(self.ram.len()..) {
+            Some(footer) if footer.len() == FOOTER_SIZE => footer,
+            _ => return
+        };
+
+        let register = |offset: usize| u16::from_le_bytes(footer[offset..offset + 2].try_into().unwrap());
+
+        self.minutes = register(8) % MINUTES_PER_DAY;
+        self.days = register(10) & 0x0FFF;
+        self.alarm_minutes = register(12);
+        self.alarm_days = register(14);
+        self.alarm_enabled = footer[16] & 0x01 != 0;
+
+        self.seconds = 0;
+        let elapsed = self.timebase.resume(u64::from_le_bytes(footer[0..8].try_into().unwrap()));
+        self.advance(elapsed);
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+
+    use crate::cartridge::Cartridge;
+    use crate::testing::rom;
+    use crate::traits::MBC;
+    use crate::mmu::mbc::rtc::CYCLES_PER_SECOND;
+    use super::{HuC3, FOOTER_SIZE};
+
+    fn read_clock(mbc: &mut HuC3) -> (u16, u16) {
+        mbc.write(0x0000, 0x0B);
+        mbc.write(0xA000, 0x40);
+        mbc.write(0xA000, 0x50);
+
+        let nibbles: Vec<u16> = (0..6).map(|_| {
+            mbc.write(0x0000, 0x0B);
+            mbc.write(0xA000, 0x10);
+            mbc.write(0x0000, 0x0C);
+            mbc.read(0xA000) as u16
+        }).collect();
+
+        (nibbles[0] | nibbles[1] << 4 | nibbles[2] << 8, nibbles[3] | nibbles[4] << 4 | nibbles[5] << 8)
+    }
+
+    #[test]
+    fn clock_commands() {
+        let mut mbc = HuC3::new(Cartridge::parse(rom(0xFE, 0x00, 0x03, &[])).unwrap()).unwrap();
+
+        // Set 23:59 (1439 = 0x59F) on day 0x123
+        mbc.write(0x0000, 0x0B);
+        mbc.write(0xA000, 0x40);
+        mbc.write(0xA000, 0x50);
+        for nibble in [0xF, 0x9, 0x5, 0x3, 0x2, 0x1].iter() {
+            mbc.write(0xA000, 0x30 | nibble);
+        }
+
+        assert_eq!(read_clock(&mut mbc), (1439, 0x123));
+
+        mbc.run(CYCLES_PER_SECOND * 60);
+        assert_eq!(read_clock(&mut mbc), (0, 0x124));
+
+        let data = mbc.save_data().unwrap();
+        assert_eq!(data.len(), 0x8000 + FOOTER_SIZE);
+
+        let mut restored = HuC3::new(Cartridge::parse(rom(0xFE, 0x00, 0x03, &[])).unwrap()).unwrap();
+        restored.load_save_data(&data);
+        assert_eq!(read_clock(&mut restored), (0, 0x124));
+    }
+}
@@ -0,0 +1,71 @@
+use crate::cartridge::Cartridge;
+use super::MBC;
+
+// Each game on an M161 multicart is a 32 KiB ROM-only game
+const BANK_SIZE: usize = 0x8000;
+
+
+/// The M161, used by Mani 4 in 1 cartridges: the menu runs from the first 32 KiB, then picks a
+/// game with a single write to 0x4000 - 0x5FFF. The choice is then locked in until power off.
+pub struct M161 {
+    cartridge: Cartridge,
+    bank: u8,
+    locked: bool
+}
+
+
+impl M161 {
+    pub fn new(cartridge: Cartridge) -> M161 {
+        M161 {
+            cartridge,
+            bank: 0,
+            locked: false
+        }
+    }
+}
+
+
+impl MBC for M161 {
+
+    fn read(&mut self, address: u16) -> u8 {
+        match address {
+            0x0000..=0x7FFF => {
+                let address = (BANK_SIZE * self.bank as usize) | address as usize;
+                self.cartridge.rom[address % self.cartridge.rom.len()]
+            },
+            _ => 0xFF
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        match address {
+            0x4000..=0x5FFF if !self.locked => {
+                self.bank = value & 0x07;
+                self.locked = true;
+            },
+            _ => ()
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+
+    use crate::cartridge::Cartridge;
+    use crate::testing::rom;
+    use crate::traits::MBC;
+    use super::M161;
+
+    #[test]
+    fn selection_is_locked() {
+        let mut data = rom(0x00, 0x00, 0x00, &[]);
+        data.resize(0x40000, 0);
+        data[0x10000] = 0x02;
+
+        let mut mbc = M161::new(Cartridge::parse(data).unwrap());
+        mbc.write(0x4000, 0x02);
+        mbc.write(0x4000, 0x05);
+        assert_eq!(mbc.read(0x0000), 0x02);
+    }
+}
@@ -18,9 +18,9 @@ impl MBC for MBC0 {
 
     fn read(&mut self, address: u16) -> u8 {
         match address {
-            0x0000..=0x7FFF => self.cartridge.rom[address as usize],
+            0x0000..=0x7FFF => *self.cartridge.rom.get(address as usize).unwrap_or(&0xFF),
             0xA000..=0xBFFF => 0,
-            _ => panic!("address not implemented for mbc0: {:#06X}", address)
+            _ => 0xFF
         }
     }
 
@@ -1,6 +1,7 @@
 use std::cmp::max;
 use crate::cartridge::Cartridge;
-use super::{MBC, create_ram, ROM_BANK_SIZE, RAM_BANK_SIZE};
+use crate::error::EmulatorError;
+use super::{MBC, create_ram, load_ram, ROM_BANK_SIZE, RAM_BANK_SIZE};
 
 
 pub struct MBC1 {
@@ -18,14 +19,14 @@ pub struct MBC1 {
 
 
 impl MBC1 {
-    pub fn new(cartridge: Cartridge) -> MBC1 {
+    pub fn new(cartridge: Cartridge) -> Result<MBC1, EmulatorError> {
 
         let ram_size = cartridge.rom[0x149];
         let rom_size = cartridge.rom_size();
 
-        let ram = create_ram(ram_size);
+        let ram = create_ram(ram_size)?;
 
-        MBC1 {
+        Ok(MBC1 {
             mode: 0,
             cartridge,
             rom_size,
@@ -35,7 +36,16 @@ impl MBC1 {
             ram,
             ram_enabled: false,
             ram_size
-        }
+        })
+    }
+
+    /// Mode 1 banks RAM with bank2; a cartridge with less than 4 banks sees its RAM mirrored
+    fn ram_address(&self, address: u16) -> usize {
+        let bank = match self.mode {
+            0 => 0,
+            _ => self.bank2 as usize
+        };
+        ((bank * RAM_BANK_SIZE) | (address as usize & (RAM_BANK_SIZE - 1))) % self.ram.len()
     }
 }
 
@@ -48,7 +58,7 @@ impl MBC for MBC1 {
             0x0000..=0x3FFF => match self.mode {
                 0 => self.cartridge.rom[address as usize],
                 1 => {
-                    let address = (ROM_BANK_SIZE * ((self.bank2 << 5) as usize)) | ((address as usize) & ROM_BANK_SIZE - 1);
+                    let address = (ROM_BANK_SIZE * ((self.bank2 << 5) as usize)) | ((address as usize) & (ROM_BANK_SIZE - 1));
                     self.cartridge.rom[address % self.cartridge.rom.len()]
                 },
                 _ => panic!("impossible mode: {}", self.mode)
@@ -56,7 +66,7 @@ impl MBC for MBC1 {
 
             // ROM Read - Banked
             0x4000..=0x7FFF => {
-                let address = (ROM_BANK_SIZE * (((self.bank2 << 5) | self.bank1) as usize)) | ((address as usize) & ROM_BANK_SIZE - 1);
+                let address = (ROM_BANK_SIZE * (((self.bank2 << 5) | self.bank1) as usize)) | ((address as usize) & (ROM_BANK_SIZE - 1));
                 self.cartridge.rom[address % self.cartridge.rom.len()]
             },
 
@@ -67,13 +77,9 @@ impl MBC for MBC1 {
                 false => 0x00,
 
                 // RAM Enabled
-                true  => match self.mode {
-                    0 => self.ram[(address as usize) & (RAM_BANK_SIZE - 1)],
-                    1 => self.ram[(self.bank2 as usize * RAM_BANK_SIZE) | ((address as usize) & (RAM_BANK_SIZE - 1))],
-                    _ => panic!("impossible mode: {}", self.mode)
-                }
+                true  => self.ram[self.ram_address(address)]
             }
-            _ => panic!("unmapped mbc1 address {:#06X}", address)
+            _ => 0xFF
         }
     }
 
@@ -81,7 +87,7 @@ impl MBC for MBC1 {
         match address {
 
             // RAM Enable
-            0x0000..=0x1FFF => if self.ram_size != 0 { self.ram_enabled = value & 0x0F == 0x0A; },
+            0x0000..=0x1FFF if self.ram_size != 0 => self.ram_enabled = value & 0x0F == 0x0A,
 
             // ROM Bank Number / Lower bits
             0x2000..=0x3FFF => self.bank1 = {
@@ -100,15 +106,25 @@ impl MBC for MBC1 {
             0x6000..=0x7FFF => self.mode = value & 0x01,
 
             // RAM Bank 00-03
-            0xA000..=0xBFFF => if self.ram_enabled {
-                match self.mode {
-                    0 => self.ram[(address as usize) & (RAM_BANK_SIZE - 1)] = value,
-                    1 => self.ram[((self.bank2 as usize) * RAM_BANK_SIZE) | (address as usize & (RAM_BANK_SIZE - 1))] = value,
-                    _ => panic!("impossible mode value: {}", self.mode)
-                }
+            0xA000..=0xBFFF if self.ram_enabled => {
+                let address = self.ram_address(address);
+                self.ram[address] = value;
             }
 
-            _ => panic!("unmapped mbc1 address {:#06X}", address)
+            _ => ()
+        }
+    }
+
+    fn save_data(&self) -> Option<Vec<u8>> {
+        match self.cartridge.kind().features.battery && !self.ram.is_empty() {
+            true  => Some(self.ram.clone()),
+            false => None
+        }
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        if self.cartridge.kind().features.battery {
+            load_ram(&mut self.ram, data);
         }
     }
 }
@@ -117,7 +133,26 @@ impl MBC for MBC1 {
 #[cfg(test)]
 mod test {
 
-    use crate::testing::mooneye_all;
+    use crate::cartridge::Cartridge;
+    use crate::testing::{mooneye_all, rom};
+    use crate::traits::MBC;
+    use super::MBC1;
+
+    #[test]
+    fn mode_1_with_one_ram_bank() {
+        let mut mbc = MBC1::new(Cartridge::parse(rom(0x03, 0x00, 0x02, &[])).unwrap()).unwrap();
+
+        // Selecting RAM bank 3 in mode 1 mirrors the only 8 KiB bank
+        mbc.write(0x0000, 0x0A);
+        mbc.write(0xA123, 0x45);
+        mbc.write(0x4000, 0x03);
+        mbc.write(0x6000, 0x01);
+        assert_eq!(mbc.read(0xA123), 0x45);
+
+        mbc.write(0xBFFF, 0x67);
+        mbc.write(0x6000, 0x00);
+        assert_eq!(mbc.read(0xBFFF), 0x67);
+    }
 
     #[test]
     fn acceptance_mbc1() {
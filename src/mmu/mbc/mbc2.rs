@@ -1,97 +1,95 @@
 use std::cmp::max;
 use crate::cartridge::Cartridge;
-use super::{MBC, create_ram, ROM_BANK_SIZE, RAM_BANK_SIZE};
+use super::{MBC, load_ram, ROM_BANK_SIZE};
+
+// MBC2 carries its own RAM: 512 cells, each only 4 bits wide
+const RAM_SIZE: usize = 0x0200;
 
-pub struct MBC2 {
-    mode: u8,
 
+pub struct MBC2 {
     cartridge: Cartridge,
     rom_bank: u8,
-    rom_size: u8,
 
     ram: Vec<u8>,
-    ram_enabled: bool,
-    ram_bank: u8,
-    ram_size: u8,
-
-    bank2: u8,
-    bank1: u8
-
+    ram_enabled: bool
 }
 
+
 impl MBC2 {
     pub fn new(cartridge: Cartridge) -> MBC2 {
-
-        let ram_size = cartridge.rom[0x149];
-        let rom_size = cartridge.rom_size();
-
-        let ram = create_ram(ram_size);
-
-
         MBC2 {
-            mode: 0,
-
             cartridge,
             rom_bank: 1,
-            rom_size,
-
-            ram,
-            ram_bank: 0,
-            ram_enabled: false,
-            ram_size,
 
-            bank2: 0,
-            bank1: 0,
+            ram: vec![0; RAM_SIZE],
+            ram_enabled: false
         }
     }
 }
 
+
 impl MBC for MBC2 {
 
     fn read(&mut self, address: u16) -> u8 {
         match address {
+            // ROM Read - Bank 0
             0x0000..=0x3FFF => self.cartridge.rom[address as usize],
+
+            // ROM Read - Banked
             0x4000..=0x7FFF => {
-                self.cartridge.rom[(address & (ROM_BANK_SIZE - 1)) as usize]
-            },
-            0xA000..=0xBFFF => match self.mode {
-                0 => self.ram[(address & (RAM_BANK_SIZE - 1)) as usize],
-                1 => self.ram[((self.ram_bank as u16 * RAM_BANK_SIZE) | (address & (RAM_BANK_SIZE - 1))) as usize],
-                _ => panic!("impossible mode: {}", self.mode)
+                let address = (ROM_BANK_SIZE * self.rom_bank as usize) | ((address as usize) & (ROM_BANK_SIZE - 1));
+                self.cartridge.rom[address % self.cartridge.rom.len()]
             },
 
-            _ => panic!("unmapped mbc1 address {:#06X}", address)
+            // RAM Read - only the lower 9 address bits are decoded, so the 512 cells repeat across
+            //  the whole area; the upper 4 bits of each cell don't exist and read as 1s
+            0xA000..=0xBFFF if self.ram_enabled => self.ram[(address as usize) & (RAM_SIZE - 1)] | 0xF0,
+
+            _ => 0xFF
         }
     }
 
     fn write(&mut self, address: u16, value: u8) {
         match address {
 
-            // RAM Enable
-            0x0000..=0x1FFF => if self.ram_size != 0 { self.ram_enabled = value & 0x0F == 0x0A; },
-
-            // ROM Bank Number
-            0x2000..=0x3FFF => self.bank1 = {
-                let mut masked = value & 0x1F;
-                if masked >= (1 << (self.rom_size + 1)) {
-                    masked &= (1 << (self.rom_size + 1)) - 1;
-                }
-
-                max(1, masked)
+            // RAM Enable / ROM Bank Number - selected by bit 8 of the address rather than the range
+            0x0000..=0x3FFF => match address & 0x0100 {
+                0 => self.ram_enabled = value & 0x0F == 0x0A,
+                _ => self.rom_bank = max(1, value & 0x0F)
             },
 
-            // RAM Bank Number / Upper Bits of ROM Bank Number
-            0x4000..=0x5FFF => self.bank2 = value & 0x03,
+            // RAM Write - 4 bits per cell
+            0xA000..=0xBFFF if self.ram_enabled => self.ram[(address as usize) & (RAM_SIZE - 1)] = value & 0x0F,
+
+            _ => ()
+        }
+    }
 
-            // Banking Mode Select
-            0x6000..=0x7FFF => self.mode = value & 0x01,
+    fn save_data(&self) -> Option<Vec<u8>> {
+        match self.cartridge.kind().features.battery {
+            true  => Some(self.ram.clone()),
+            false => None
+        }
+    }
 
-            // RAM Bank 00-03
-            0xA000..=0xBFFF => if self.ram_enabled {
-                self.ram[((self.ram_bank as u16 * RAM_BANK_SIZE) | (address & (RAM_BANK_SIZE - 1))) as usize] = value;
-            }
+    fn load_save_data(&mut self, data: &[u8]) {
+        if self.cartridge.kind().features.battery {
+            load_ram(&mut self.ram, data);
 
-            _ => panic!("unmapped mbc1 address {:#06X}", address)
+            // Only the lower 4 bits of each cell exist
+            self.ram.iter_mut().for_each(|cell| *cell &= 0x0F);
         }
     }
-}
\ No newline at end of file
+}
+
+
+#[cfg(test)]
+mod test {
+
+    use crate::testing::mooneye_all;
+
+    #[test]
+    fn acceptance_mbc2() {
+        mooneye_all("emulator-only/mbc2");
+    }
+}
@@ -0,0 +1,224 @@
+use std::cmp::max;
+use crate::cartridge::Cartridge;
+use crate::error::EmulatorError;
+use super::{MBC, create_ram, load_ram, ROM_BANK_SIZE, RAM_BANK_SIZE};
+use super::rtc::{ClockSource, RTC};
+
+
+pub struct MBC3 {
+    cartridge: Cartridge,
+    rom_bank: u8,
+
+    ram: Vec<u8>,
+    ram_enabled: bool,      // Also enables access to the RTC registers
+
+    // 0x00 - 0x03 select a RAM bank, 0x08 - 0x0C an RTC register
+    bank_select: u8,
+
+    // Only cartridge types 0x0F - 0x10 have the clock
+    rtc: Option<RTC>,
+    latch_value: u8         // Last value written to 0x6000 - 0x7FFF; 0x00 then 0x01 latches
+}
+
+
+impl MBC3 {
+    pub fn new(cartridge: Cartridge) -> Result<MBC3, EmulatorError> {
+
+        let ram = create_ram(cartridge.rom[0x149])?;
+        let rtc = match cartridge.kind().features.timer {
+            true  => Some(RTC::new()),
+            false => None
+        };
+
+        Ok(MBC3 {
+            cartridge,
+            rom_bank: 1,
+
+            ram,
+            ram_enabled: false,
+            bank_select: 0,
+
+            rtc,
+            latch_value: 0xFF
+        })
+    }
+
+    fn ram_address(&self, address: u16) -> usize {
+        ((self.bank_select as usize * RAM_BANK_SIZE) | (address as usize & (RAM_BANK_SIZE - 1))) % self.ram.len()
+    }
+}
+
+
+impl MBC for MBC3 {
+
+    fn read(&mut self, address: u16) -> u8 {
+        match address {
+
+            // ROM Read - Bank 0
+            0x0000..=0x3FFF => self.cartridge.rom[address as usize],
+
+            // ROM Read - Banked
+            0x4000..=0x7FFF => {
+                let address = (ROM_BANK_SIZE * self.rom_bank as usize) | (address as usize & (ROM_BANK_SIZE - 1));
+                self.cartridge.rom[address % self.cartridge.rom.len()]
+            },
+
+            // RAM Read / RTC Register Read
+            0xA000..=0xBFFF if self.ram_enabled => match (self.bank_select, &self.rtc) {
+                (0x00..=0x03, _) if !self.ram.is_empty() => self.ram[self.ram_address(address)],
+                (0x08..=0x0C, Some(rtc)) => rtc.read(self.bank_select),
+                _ => 0xFF
+            },
+
+            _ => 0xFF
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        match address {
+
+            // RAM and Timer Enable
+            0x0000..=0x1FFF => self.ram_enabled = value & 0x0F == 0x0A,
+
+            // ROM Bank Number - 7 bits, where 0 selects bank 1
+            0x2000..=0x3FFF => self.rom_bank = max(1, value & 0x7F),
+
+            // RAM Bank Number / RTC Register Select
+            0x4000..=0x5FFF => self.bank_select = value & 0x0F,
+
+            // Latch Clock Data - writing 0x00 and then 0x01 copies the clock into the RTC registers
+            0x6000..=0x7FFF => {
+                if let (0x00, 0x01, Some(rtc)) = (self.latch_value, value, &mut self.rtc) {
+                    rtc.latch();
+                }
+                self.latch_value = value;
+            },
+
+            // RAM Write / RTC Register Write
+            0xA000..=0xBFFF if self.ram_enabled => match self.bank_select {
+                0x00..=0x03 if !self.ram.is_empty() => {
+                    let address = self.ram_address(address);
+                    self.ram[address] = value;
+                },
+                0x08..=0x0C => if let Some(rtc) = &mut self.rtc {
+                    rtc.write(self.bank_select, value);
+                },
+                _ => ()
+            },
+
+            _ => ()
+        }
+    }
+
+    fn run(&mut self, clock_cycles: u64) {
+        if let Some(rtc) = &mut self.rtc {
+            rtc.run(clock_cycles);
+        }
+    }
+
+    fn set_clock_source(&mut self, source: ClockSource) {
+        if let Some(rtc) = &mut self.rtc {
+            rtc.set_source(source);
+        }
+    }
+
+    fn save_data(&self) -> Option<Vec<u8>> {
+
+        if !self.cartridge.kind().features.battery || (self.ram.is_empty() && self.rtc.is_none()) {
+            return None;
+        }
+
+        // The clock's state follows the RAM
+        let mut data = self.ram.clone();
+        if let Some(rtc) = &self.rtc {
+            data.extend_from_slice(&rtc.footer());
+        }
+
+        Some(data)
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+
+        if !self.cartridge.kind().features.battery {
+            return;
+        }
+
+        load_ram(&mut self.ram, data);
+
+        // A save from a cartridge without a clock (or an emulator that doesn't store one) just
+        //  leaves the clock as it is
+        if let (Some(rtc), Some(footer)) = (&mut self.rtc, data.get(self.ram.len()..)) {
+            rtc.load_footer(footer);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+
+    use crate::cartridge::Cartridge;
+    use crate::testing::rom;
+    use crate::traits::MBC;
+    use super::MBC3;
+
+    #[test]
+    fn rom_and_ram_banking() {
+        let mut data = rom(0x13, 0x06, 0x03, &[]);
+        data[0x7F * 0x4000] = 0x7F;
+
+        let mut mbc = MBC3::new(Cartridge::parse(data).unwrap()).unwrap();
+
+        mbc.write(0x2000, 0xFF);
+        assert_eq!(mbc.read(0x4000), 0x7F);
+
+        mbc.write(0x0000, 0x0A);
+        for bank in 0..4 {
+            mbc.write(0x4000, bank);
+            mbc.write(0xA000, bank + 1);
+        }
+        mbc.write(0x4000, 0x02);
+        assert_eq!(mbc.read(0xA000), 0x03);
+
+        // No clock on this cartridge type
+        mbc.write(0x4000, 0x08);
+        assert_eq!(mbc.read(0xA000), 0xFF);
+    }
+
+    #[test]
+    fn rtc_latch() {
+        let mut mbc = MBC3::new(Cartridge::parse(rom(0x10, 0x00, 0x03, &[])).unwrap()).unwrap();
+        mbc.write(0x0000, 0x0A);
+        mbc.write(0x4000, 0x09);
+        mbc.write(0xA000, 0x05);
+
+        mbc.run(4_194_304 * 60);
+        assert_eq!(mbc.read(0xA000), 0x05);
+
+        mbc.write(0x6000, 0x00);
+        mbc.write(0x6000, 0x01);
+        assert_eq!(mbc.read(0xA000), 0x06);
+    }
+
+    #[test]
+    fn save_includes_rtc() {
+        let mut mbc = MBC3::new(Cartridge::parse(rom(0x10, 0x00, 0x02, &[])).unwrap()).unwrap();
+        mbc.write(0x0000, 0x0A);
+        mbc.write(0xA000, 0x42);
+        mbc.write(0x4000, 0x0A);
+        mbc.write(0xA000, 0x07);
+
+        let data = mbc.save_data().unwrap();
+        assert_eq!(data.len(), 0x2000 + 48);
+
+        let mut restored = MBC3::new(Cartridge::parse(rom(0x10, 0x00, 0x02, &[])).unwrap()).unwrap();
+        restored.load_save_data(&data);
+        restored.write(0x0000, 0x0A);
+        restored.write(0x6000, 0x00);
+        restored.write(0x6000, 0x01);
+        restored.write(0x4000, 0x0A);
+        assert_eq!(restored.read(0xA000), 0x07);
+        restored.write(0x4000, 0x00);
+        assert_eq!(restored.read(0xA000), 0x42);
+    }
+}
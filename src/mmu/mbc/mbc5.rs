@@ -1,5 +1,6 @@
 use crate::cartridge::Cartridge;
-use super::{MBC, create_ram, ROM_BANK_SIZE, RAM_BANK_SIZE};
+use crate::error::EmulatorError;
+use super::{MBC, create_ram, load_ram, ROM_BANK_SIZE, RAM_BANK_SIZE};
 
 
 pub struct MBC5 {
@@ -11,16 +12,20 @@ pub struct MBC5 {
     ram_enabled: bool,
     ram_bank: usize,
 
-    rumble: bool
+    // Only rumble cartridges (0x1C - 0x1E) have the motor, which takes over bit 3 of the RAM bank
+    has_rumble: bool,
+    rumble: bool,           // Motor currently on
+    rumbled: bool           // Motor switched on since the frontend last asked
 }
 
 
 impl MBC5 {
-    pub fn new(cartridge: Cartridge) -> MBC5 {
+    pub fn new(cartridge: Cartridge) -> Result<MBC5, EmulatorError> {
 
-        let ram = create_ram(cartridge.rom[0x149]);
+        let ram = create_ram(cartridge.rom[0x149])?;
+        let has_rumble = cartridge.kind().features.rumble;
 
-        MBC5 {
+        Ok(MBC5 {
             cartridge,
             bank1: 1,
             bank2: 0,
@@ -29,8 +34,10 @@ impl MBC5 {
             ram_enabled: false,
             ram_bank: 0,
 
-            rumble: false
-        }
+            has_rumble,
+            rumble: false,
+            rumbled: false
+        })
     }
 }
 
@@ -46,7 +53,8 @@ impl MBC for MBC5 {
             // ROM Read - Banked
             0x4000..=0x7FFF => {
                 let bank_number = (((self.bank2 as u16) << 8) | self.bank1 as u16) as usize;
-                self.cartridge.rom[ROM_BANK_SIZE * bank_number | (address as usize & (ROM_BANK_SIZE - 1))]
+                let address = (ROM_BANK_SIZE * bank_number) | (address as usize & (ROM_BANK_SIZE - 1));
+                self.cartridge.rom[address % self.cartridge.rom.len()]
             },
 
             // RAM Read
@@ -56,9 +64,12 @@ impl MBC for MBC5 {
                 false => 0x00,
 
                 // RAM Enabled
-                true  => self.ram[RAM_BANK_SIZE * self.ram_bank | ((address as usize) & (RAM_BANK_SIZE - 1))]
+                true  => match self.ram.len() {
+                    0 => 0xFF,
+                    n => self.ram[((RAM_BANK_SIZE * self.ram_bank) | ((address as usize) & (RAM_BANK_SIZE - 1))) % n]
+                }
             }
-            _ => panic!("unmapped MBC5 address {:#06X}", address)
+            _ => 0xFF
         }
     }
 
@@ -74,18 +85,42 @@ impl MBC for MBC5 {
             // ROM Bank Number / 9th bit
             0x3000..=0x3FFF => self.bank2 = value & 0x01,
 
-            // RAM Bank Number
-            0x4000..=0x5FFF => {
-                self.rumble = (value & 0x04) != 0;
-                self.ram_bank = (value & 0x03) as usize
+            // RAM Bank Number - on rumble cartridges, bit 3 switches the motor instead
+            0x4000..=0x5FFF => match self.has_rumble {
+                true => {
+                    self.rumble = value & 0x08 != 0;
+                    self.rumbled |= self.rumble;
+                    self.ram_bank = (value & 0x07) as usize;
+                },
+                false => self.ram_bank = (value & 0x0F) as usize
             },
 
-            // RAM Bank 00-03
-            0xA000..=0xBFFF => if self.ram_enabled {
-                self.ram[RAM_BANK_SIZE * self.ram_bank | ((address as usize) & (RAM_BANK_SIZE - 1))] = value
+            // RAM Bank 00-0F
+            0xA000..=0xBFFF if self.ram_enabled && !self.ram.is_empty() => {
+                let n = self.ram.len();
+                self.ram[((RAM_BANK_SIZE * self.ram_bank) | ((address as usize) & (RAM_BANK_SIZE - 1))) % n] = value
             }
 
-            _ => panic!("unmapped MBC5 address {:#06X}", address)
+            _ => ()
+        }
+    }
+
+    fn take_rumble(&mut self) -> bool {
+        let rumbled = self.rumbled || self.rumble;
+        self.rumbled = false;
+        rumbled
+    }
+
+    fn save_data(&self) -> Option<Vec<u8>> {
+        match self.cartridge.kind().features.battery && !self.ram.is_empty() {
+            true  => Some(self.ram.clone()),
+            false => None
+        }
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        if self.cartridge.kind().features.battery {
+            load_ram(&mut self.ram, data);
         }
     }
 }
@@ -94,10 +129,40 @@ impl MBC for MBC5 {
 #[cfg(test)]
 mod test {
 
-    use crate::testing::mooneye_all;
+    use crate::cartridge::Cartridge;
+    use crate::testing::{mooneye_all, rom};
+    use crate::traits::MBC;
+    use super::MBC5;
 
     #[test]
     fn acceptance_mbc5() {
         mooneye_all("emulator-only/mbc5");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn rumble_and_ram_banks() {
+
+        // 128 KiB of RAM; bank 0x0F is only reachable without rumble
+        let mut mbc = MBC5::new(Cartridge::parse(rom(0x1B, 0x00, 0x04, &[])).unwrap()).unwrap();
+        mbc.write(0x0000, 0x0A);
+        mbc.write(0x4000, 0x0F);
+        mbc.write(0xA000, 0x0F);
+        mbc.write(0x4000, 0x07);
+        mbc.write(0xA000, 0x07);
+        mbc.write(0x4000, 0x0F);
+        assert_eq!(mbc.read(0xA000), 0x0F);
+        assert!(!mbc.take_rumble());
+
+        let mut mbc = MBC5::new(Cartridge::parse(rom(0x1E, 0x00, 0x03, &[])).unwrap()).unwrap();
+        mbc.write(0x0000, 0x0A);
+        mbc.write(0x4000, 0x01);
+        mbc.write(0xA000, 0x01);
+        mbc.write(0x4000, 0x09);
+        assert_eq!(mbc.read(0xA000), 0x01);
+
+        // A pulse that has already ended still counts, once
+        mbc.write(0x4000, 0x01);
+        assert!(mbc.take_rumble());
+        assert!(!mbc.take_rumble());
+    }
+}
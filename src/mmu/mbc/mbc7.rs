@@ -0,0 +1,373 @@
+use crate::cartridge::Cartridge;
+use crate::joypad::Tilt;
+use super::{MBC, load_ram, ROM_BANK_SIZE};
+
+// The accelerometer reads 0x81D0 when level on each axis, moving by about 0x70 per g
+const ACCELEROMETER_CENTRE: f32 = 0x81D0 as f32;
+const ACCELEROMETER_PER_G: f32 = 0x70 as f32;
+
+// The 93LC56 holds 128 16-bit words
+const EEPROM_WORDS: usize = 128;
+
+
+/// MBC7, as used by Kirby Tilt 'n' Tumble and Command Master: a two-axis accelerometer and a
+/// serial EEPROM (instead of RAM) mapped as registers into 0xA000 - 0xAFFF
+pub struct MBC7 {
+    cartridge: Cartridge,
+    rom_bank: u8,
+
+    // Both halves have to be enabled before the registers can be reached
+    ram_enabled_1: bool,    // 0x0A written to 0x0000 - 0x1FFF
+    ram_enabled_2: bool,    // 0x40 written to 0x4000 - 0x5FFF
+
+    tilt: Tilt,
+    accelerometer_x: u16,
+    accelerometer_y: u16,
+    latched: bool,
+
+    eeprom: EEPROM
+}
+
+
+impl MBC7 {
+    pub fn new(cartridge: Cartridge) -> MBC7 {
+        MBC7 {
+            cartridge,
+            rom_bank: 1,
+
+            ram_enabled_1: false,
+            ram_enabled_2: false,
+
+            tilt: Tilt::default(),
+            accelerometer_x: 0x8000,
+            accelerometer_y: 0x8000,
+            latched: false,
+
+            eeprom: EEPROM::new()
+        }
+    }
+}
+
+
+impl MBC for MBC7 {
+
+    fn read(&mut self, address: u16) -> u8 {
+        match address {
+
+            // ROM Read - Bank 0
+            0x0000..=0x3FFF => self.cartridge.rom[address as usize],
+
+            // ROM Read - Banked
+            0x4000..=0x7FFF => {
+                let address = (ROM_BANK_SIZE * self.rom_bank as usize) | (address as usize & (ROM_BANK_SIZE - 1));
+                self.cartridge.rom[address % self.cartridge.rom.len()]
+            },
+
+            // Registers - selected by bits 4-7 of the address, and repeated through the area
+            0xA000..=0xAFFF if self.ram_enabled_1 && self.ram_enabled_2 => match (address >> 4) & 0x0F {
+                0x2 => self.accelerometer_x as u8,
+                0x3 => (self.accelerometer_x >> 8) as u8,
+                0x4 => self.accelerometer_y as u8,
+                0x5 => (self.accelerometer_y >> 8) as u8,
+                0x6 => 0x00,
+                0x8 => self.eeprom.read(),
+                _ => 0xFF
+            },
+
+            _ => 0xFF
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        match address {
+
+            // RAM Enable 1
+            0x0000..=0x1FFF => {
+                self.ram_enabled_1 = value & 0x0F == 0x0A;
+                if !self.ram_enabled_1 {
+                    self.ram_enabled_2 = false;
+                }
+            },
+
+            // ROM Bank Number
+            0x2000..=0x3FFF => self.rom_bank = value & 0x7F,
+
+            // RAM Enable 2 - only takes effect once the first half is enabled
+            0x4000..=0x5FFF => self.ram_enabled_2 = self.ram_enabled_1 && value == 0x40,
+
+            0xA000..=0xAFFF if self.ram_enabled_1 && self.ram_enabled_2 => match (address >> 4) & 0x0F {
+
+                // Erase the accelerometer reading, ready for a new one
+                0x0 if value == 0x55 => {
+                    self.accelerometer_x = 0x8000;
+                    self.accelerometer_y = 0x8000;
+                    self.latched = false;
+                },
+
+                // Latch the accelerometer; only once after each erase
+                0x1 if value == 0xAA && !self.latched => {
+                    self.accelerometer_x = (ACCELEROMETER_CENTRE - self.tilt.x * ACCELEROMETER_PER_G) as u16;
+                    self.accelerometer_y = (ACCELEROMETER_CENTRE + self.tilt.y * ACCELEROMETER_PER_G) as u16;
+                    self.latched = true;
+                },
+
+                0x8 => self.eeprom.write(value),
+
+                _ => ()
+            },
+
+            _ => ()
+        }
+    }
+
+    fn set_tilt(&mut self, tilt: Tilt) {
+        self.tilt = tilt;
+    }
+
+    fn save_data(&self) -> Option<Vec<u8>> {
+        Some(self.eeprom.bytes())
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        let mut bytes = self.eeprom.bytes();
+        load_ram(&mut bytes, data);
+        self.eeprom.load_bytes(&bytes);
+    }
+}
+
+
+/// Where the EEPROM is in receiving or answering a command
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum EEPROMState {
+    Idle,                                           // Waiting for a start bit
+    Command { bits: u8, shift: u16 },               // Receiving the opcode and address
+    Read { address: u8, bits: u8 },                 // Shifting a word out
+    Write { address: Option<u8>, bits: u8, shift: u16 },   // Receiving a word; no address = all
+    Done                                            // Waiting for chip select to drop
+}
+
+
+/// The 93LC56 serial EEPROM, organised as 16-bit words. It's driven a bit at a time through a
+/// single register: chip select (bit 7), clock (bit 6) and data in (bit 1) are written, and data
+/// out (bit 0) read back. Every command is a 1 start bit followed by a 2-bit opcode and an 8-bit
+/// address (of which 7 bits are used), clocked in on rising edges.
+struct EEPROM {
+    words: [u16; EEPROM_WORDS],
+    write_enabled: bool,
+
+    chip_select: bool,
+    clock: bool,
+    data_in: bool,
+    data_out: bool,
+
+    state: EEPROMState
+}
+
+
+impl EEPROM {
+
+    fn new() -> EEPROM {
+        EEPROM {
+            words: [0xFFFF; EEPROM_WORDS],
+            write_enabled: false,
+
+            chip_select: false,
+            clock: false,
+            data_in: false,
+            data_out: true,
+
+            state: EEPROMState::Idle
+        }
+    }
+
+    fn read(&self) -> u8 {
+        (self.chip_select as u8) << 7 | (self.clock as u8) << 6 | (self.data_in as u8) << 1 | self.data_out as u8
+    }
+
+    fn write(&mut self, value: u8) {
+
+        let rising_edge = !self.clock && value & 0x40 != 0;
+
+        self.chip_select = value & 0x80 != 0;
+        self.clock = value & 0x40 != 0;
+        self.data_in = value & 0x02 != 0;
+
+        // Dropping chip select abandons anything in progress
+        if !self.chip_select {
+            self.state = EEPROMState::Idle;
+            self.data_out = true;
+            return;
+        }
+
+        if rising_edge {
+            self.clock_in(self.data_in as u16);
+        }
+    }
+
+    fn clock_in(&mut self, bit: u16) {
+        self.state = match self.state {
+
+            EEPROMState::Idle if bit == 1 => EEPROMState::Command { bits: 0, shift: 0 },
+            EEPROMState::Idle => EEPROMState::Idle,
+
+            EEPROMState::Command { bits: 9, shift } => self.command((shift << 1) | bit),
+            EEPROMState::Command { bits, shift } => EEPROMState::Command { bits: bits + 1, shift: (shift << 1) | bit },
+
+            // Reads carry on into the following word, for as long as the clock keeps running
+            EEPROMState::Read { address, bits } => {
+                self.data_out = self.words[address as usize] & (0x8000 >> bits) != 0;
+                match bits {
+                    15 => EEPROMState::Read { address: (address + 1) % EEPROM_WORDS as u8, bits: 0 },
+                    _ => EEPROMState::Read { address, bits: bits + 1 }
+                }
+            },
+
+            EEPROMState::Write { address, bits: 15, shift } => {
+                let word = (shift << 1) | bit;
+                if self.write_enabled {
+                    match address {
+                        Some(address) => self.words[address as usize] = word,
+                        None => self.words = [word; EEPROM_WORDS]
+                    }
+                }
+                self.data_out = true;
+                EEPROMState::Done
+            },
+            EEPROMState::Write { address, bits, shift } => EEPROMState::Write { address, bits: bits + 1, shift: (shift << 1) | bit },
+
+            EEPROMState::Done => EEPROMState::Done
+        }
+    }
+
+    /// Act on a complete 10-bit command (opcode and address)
+    fn command(&mut self, command: u16) -> EEPROMState {
+
+        let address = (command & 0x7F) as u8;
+
+        match (command >> 8, (command >> 6) & 0x03) {
+
+            // READ - a dummy 0 comes out first, then the word
+            (0b10, _) => {
+                self.data_out = false;
+                EEPROMState::Read { address, bits: 0 }
+            },
+
+            // WRITE
+            (0b01, _) => EEPROMState::Write { address: Some(address), bits: 0, shift: 0 },
+
+            // ERASE
+            (0b11, _) => {
+                if self.write_enabled {
+                    self.words[address as usize] = 0xFFFF;
+                }
+                EEPROMState::Done
+            },
+
+            // EWDS - Erase / Write Disable
+            (0b00, 0b00) => {
+                self.write_enabled = false;
+                EEPROMState::Done
+            },
+
+            // WRAL - Write All
+            (0b00, 0b01) => EEPROMState::Write { address: None, bits: 0, shift: 0 },
+
+            // ERAL - Erase All
+            (0b00, 0b10) => {
+                if self.write_enabled {
+                    self.words = [0xFFFF; EEPROM_WORDS];
+                }
+                EEPROMState::Done
+            },
+
+            // EWEN - Erase / Write Enable
+            _ => {
+                self.write_enabled = true;
+                EEPROMState::Done
+            }
+        }
+    }
+
+    /// The words as bytes, little-endian, as other emulators save them
+    fn bytes(&self) -> Vec<u8> {
+        self.words.iter().flat_map(|word| word.to_le_bytes().to_vec()).collect()
+    }
+
+    fn load_bytes(&mut self, bytes: &[u8]) {
+        for (word, pair) in self.words.iter_mut().zip(bytes.chunks(2)) {
+            *word = pair[0] as u16 | (pair[1] as u16) << 8;
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+
+    use crate::cartridge::Cartridge;
+    use crate::joypad::Tilt;
+    use crate::testing::rom;
+    use crate::traits::MBC;
+    use super::MBC7;
+
+    fn enabled() -> MBC7 {
+        let mut mbc = MBC7::new(Cartridge::parse(rom(0x22, 0x00, 0x00, &[])).unwrap());
+        mbc.write(0x0000, 0x0A);
+        mbc.write(0x4000, 0x40);
+        mbc
+    }
+
+    /// Clock bits into the EEPROM, returning data out after each rising edge
+    fn send(mbc: &mut MBC7, bits: &[u8]) -> Vec<u8> {
+        bits.iter().map(|bit| {
+            mbc.write(0xA080, 0x80 | bit << 1);
+            mbc.write(0xA080, 0xC0 | bit << 1);
+            mbc.read(0xA080) & 0x01
+        }).collect()
+    }
+
+    fn bits(value: u16, count: usize) -> Vec<u8> {
+        (0..count).rev().map(|i| ((value >> i) & 1) as u8).collect()
+    }
+
+    /// A start bit, then the 2-bit opcode and 8-bit address
+    fn command(opcode: u16, address: u16) -> Vec<u8> {
+        [&[1][..], &bits(opcode << 8 | address, 10)].concat()
+    }
+
+    #[test]
+    fn accelerometer() {
+        let mut mbc = enabled();
+        mbc.set_tilt(Tilt { x: 1.0, y: 0.0 });
+
+        mbc.write(0xA000, 0x55);
+        mbc.write(0xA010, 0xAA);
+        assert_eq!((mbc.read(0xA030), mbc.read(0xA020)), (0x81, 0xD0 - 0x70));
+        assert_eq!((mbc.read(0xA050), mbc.read(0xA040)), (0x81, 0xD0));
+
+        // Latching again without erasing first keeps the old reading
+        mbc.set_tilt(Tilt { x: 0.0, y: 0.0 });
+        mbc.write(0xA010, 0xAA);
+        assert_eq!(mbc.read(0xA020), 0xD0 - 0x70);
+    }
+
+    #[test]
+    fn eeprom_write_and_read() {
+        let mut mbc = enabled();
+
+        // EWEN, then WRITE 0xBEEF to word 5
+        send(&mut mbc, &command(0b00, 0xC0));
+        mbc.write(0xA080, 0x00);
+        send(&mut mbc, &[command(0b01, 0x05), bits(0xBEEF, 16)].concat());
+        mbc.write(0xA080, 0x00);
+
+        // READ word 5
+        let out = send(&mut mbc, &[command(0b10, 0x05), vec![0; 16]].concat());
+        assert_eq!(out[10], 0);
+        assert_eq!(out[11..], bits(0xBEEF, 16)[..]);
+
+        let save = mbc.save_data().unwrap();
+        assert_eq!(save.len(), 256);
+        assert_eq!(save[10..12], [0xEF, 0xBE]);
+    }
+}
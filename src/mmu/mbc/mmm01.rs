@@ -0,0 +1,219 @@
+use crate::cartridge::Cartridge;
+use crate::cartridge::header::MIN_ROM_SIZE;
+use crate::error::EmulatorError;
+use super::{MBC, create_ram, load_ram, ROM_BANK_SIZE, RAM_BANK_SIZE};
+
+
+/// MMM01, used by multicart compilations. It starts "unmapped", running the menu from the last
+/// 32 KiB of ROM; the menu then sets up the outer bank bits and bank masks for a game, and
+/// "maps" it, after which the cartridge behaves like an MBC1 confined to that game's banks. The
+/// outer bits and masks can only be written while unmapped.
+pub struct MMM01 {
+    cartridge: Cartridge,
+    menu: usize,            // Where the menu's 32 KiB starts in the ROM
+    mapped: bool,
+
+    rom_bank_low: u8,       // Bits 0-4, as on an MBC1
+    rom_bank_mid: u8,       // Bits 5-6
+    rom_bank_high: u8,      // Bits 7-8
+    rom_bank_mask: u8,      // Bits of rom_bank_low the game can't change
+
+    ram: Vec<u8>,
+    ram_enabled: bool,
+    ram_bank_low: u8,       // Bits 0-1
+    ram_bank_high: u8,      // Bits 2-3
+    ram_bank_mask: u8,      // Bits of ram_bank_low the game can't change
+
+    mode: u8,
+    mode_locked: bool
+}
+
+
+impl MMM01 {
+    pub fn new(cartridge: Cartridge) -> Result<MMM01, EmulatorError> {
+
+        // The menu's header, which describes the cartridge as a whole, is in the last 32 KiB
+        //  unless the ROM has been rearranged to put it first
+        let menu = match cartridge.cartridge_type() {
+            0x0B..=0x0D => 0,
+            _ => cartridge.rom.len() - MIN_ROM_SIZE
+        };
+        let ram = create_ram(cartridge.rom[menu + 0x149])?;
+
+        Ok(MMM01 {
+            cartridge,
+            menu,
+            mapped: false,
+
+            rom_bank_low: 0,
+            rom_bank_mid: 0,
+            rom_bank_high: 0,
+            rom_bank_mask: 0,
+
+            ram,
+            ram_enabled: false,
+            ram_bank_low: 0,
+            ram_bank_high: 0,
+            ram_bank_mask: 0,
+
+            mode: 0,
+            mode_locked: false
+        })
+    }
+
+    fn rom_read(&self, bank: usize, address: u16) -> u8 {
+        let address = (ROM_BANK_SIZE * bank) | (address as usize & (ROM_BANK_SIZE - 1));
+        self.cartridge.rom[address % self.cartridge.rom.len()]
+    }
+
+    /// The game's outer bank bits, which select where in the ROM it starts
+    fn rom_bank_base(&self) -> usize {
+        (self.rom_bank_high as usize) << 7 | (self.rom_bank_mid as usize) << 5 | (self.rom_bank_low & self.rom_bank_mask) as usize
+    }
+
+    fn ram_address(&self, address: u16) -> usize {
+
+        // As on an MBC1, the RAM bank number only applies in mode 1
+        let low = match self.mode {
+            0 => self.ram_bank_low & self.ram_bank_mask,
+            _ => self.ram_bank_low
+        };
+        let bank = (self.ram_bank_high << 2 | low) as usize;
+        ((bank * RAM_BANK_SIZE) | (address as usize & (RAM_BANK_SIZE - 1))) % self.ram.len()
+    }
+}
+
+
+impl MBC for MMM01 {
+
+    fn read(&mut self, address: u16) -> u8 {
+        match address {
+
+            // ROM Read - Unmapped, the menu
+            0x0000..=0x7FFF if !self.mapped => self.cartridge.rom[self.menu + address as usize],
+
+            // ROM Read - Bank 0 of the game
+            0x0000..=0x3FFF => self.rom_read(self.rom_bank_base(), address),
+
+            // ROM Read - Banked, where as on an MBC1 selecting bank 0 (of the bits the game can
+            //  change) gives bank 1
+            0x4000..=0x7FFF => {
+                let low = match self.rom_bank_low & !self.rom_bank_mask & 0x1F {
+                    0 => self.rom_bank_low | 0x01,
+                    _ => self.rom_bank_low
+                };
+                self.rom_read(self.rom_bank_base() & !0x1F | low as usize, address)
+            },
+
+            // RAM Read
+            0xA000..=0xBFFF if self.ram_enabled && !self.ram.is_empty() => self.ram[self.ram_address(address)],
+
+            _ => 0xFF
+        }
+    }
+
+    fn write(&mut self, address: u16, value: u8) {
+        match address {
+
+            // RAM Enable / RAM Bank Mask / Map Enable
+            0x0000..=0x1FFF => {
+                self.ram_enabled = value & 0x0F == 0x0A;
+                if !self.mapped {
+                    self.ram_bank_mask = (value >> 4) & 0x03;
+                    self.mapped = value & 0x40 != 0;
+                }
+            },
+
+            // ROM Bank Number / ROM Bank Mid
+            0x2000..=0x3FFF => match self.mapped {
+                true => self.rom_bank_low = (self.rom_bank_low & self.rom_bank_mask) | (value & 0x1F & !self.rom_bank_mask),
+                false => {
+                    self.rom_bank_low = value & 0x1F;
+                    self.rom_bank_mid = (value >> 5) & 0x03;
+                }
+            },
+
+            // RAM Bank Number / RAM Bank High / ROM Bank High / Mode Lock
+            0x4000..=0x5FFF => match self.mapped {
+                true => self.ram_bank_low = (self.ram_bank_low & self.ram_bank_mask) | (value & 0x03 & !self.ram_bank_mask),
+                false => {
+                    self.ram_bank_low = value & 0x03;
+                    self.ram_bank_high = (value >> 2) & 0x03;
+                    self.rom_bank_high = (value >> 4) & 0x03;
+                    self.mode_locked = value & 0x40 != 0;
+                }
+            },
+
+            // Banking Mode Select / ROM Bank Mask
+            0x6000..=0x7FFF => {
+                if !self.mode_locked {
+                    self.mode = value & 0x01;
+                }
+                if !self.mapped {
+                    self.rom_bank_mask = (value & 0x3C) >> 1;
+                }
+            },
+
+            // RAM Write
+            0xA000..=0xBFFF if self.ram_enabled && !self.ram.is_empty() => {
+                let address = self.ram_address(address);
+                self.ram[address] = value;
+            },
+
+            _ => ()
+        }
+    }
+
+    fn save_data(&self) -> Option<Vec<u8>> {
+        match self.cartridge.kind().features.battery && !self.ram.is_empty() {
+            true  => Some(self.ram.clone()),
+            false => None
+        }
+    }
+
+    fn load_save_data(&mut self, data: &[u8]) {
+        if self.cartridge.kind().features.battery {
+            load_ram(&mut self.ram, data);
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+
+    use crate::cartridge::Cartridge;
+    use crate::testing::rom;
+    use crate::traits::MBC;
+    use super::MMM01;
+
+    #[test]
+    fn menu_then_mapped_game() {
+
+        // 256 KiB: the menu in the last 32 KiB, and a game starting at bank 0x04
+        let mut data = rom(0x01, 0x03, 0x00, &[]);
+        let menu = rom(0x0B, 0x00, 0x00, &[]);
+        let start = data.len() - menu.len();
+        data[start..].copy_from_slice(&menu);
+        data[0x04 * 0x4000] = 0x04;
+        data[0x05 * 0x4000] = 0x05;
+        data[0x06 * 0x4000] = 0x06;
+
+        let mut mbc = MMM01::new(Cartridge::parse(data).unwrap()).unwrap();
+        assert_eq!(mbc.read(0x0134), b'T');
+
+        // Lock bit 2 of the bank number (the game is 4 banks from bank 4), then map
+        mbc.write(0x6000, 0x04 << 1);
+        mbc.write(0x2000, 0x04);
+        mbc.write(0x0000, 0x40);
+
+        assert_eq!(mbc.read(0x0000), 0x04);
+        assert_eq!(mbc.read(0x4000), 0x05);
+
+        // The game can't reach outside its banks
+        mbc.write(0x2000, 0x02);
+        assert_eq!(mbc.read(0x4000), 0x06);
+        mbc.write(0x2000, 0x00);
+        assert_eq!(mbc.read(0x4000), 0x05);
+    }
+}
@@ -1,35 +1,70 @@
+use std::cmp::min;
+
 use crate::cartridge::Cartridge;
+use crate::cartridge::metadata::Mapper;
+use crate::error::EmulatorError;
 use crate::traits::MBC;
 
+pub mod camera;
+pub mod huc1;
+pub mod huc3;
 pub mod mbc0;
 pub mod mbc1;
+pub mod mbc2;
+pub mod mbc3;
 pub mod mbc5;
+pub mod mbc7;
+pub mod m161;
+pub mod mmm01;
+pub mod rtc;
+pub mod wisdom_tree;
 
 const ROM_BANK_SIZE: usize = 0x4000;
 const RAM_BANK_SIZE: usize = 0x2000;
 
 
 // Create RAM for an MBC given the RAM size code in the cartridge
-pub fn create_ram(ram_size: u8) -> Vec<u8> {
-    std::iter::repeat(0).take(match ram_size {
+pub fn create_ram(ram_size: u8) -> Result<Vec<u8>, EmulatorError> {
+    Ok(vec![0; match ram_size {
         0 => 0,
         2 => 1,
         3 => 4,
         4 => 16,
         5 => 8,
 
-        _ => panic!("impossible ram size: {}", ram_size)
-    } * (RAM_BANK_SIZE as usize)).collect()
+        _ => return Err(EmulatorError::InvalidRamSize { code: ram_size })
+    } * RAM_BANK_SIZE])
+}
+
+/// Copy a save into cartridge RAM; one of the wrong size (from a different revision of the game,
+///  or an emulator that pads its saves) is loaded as far as it fits
+pub fn load_ram(ram: &mut [u8], data: &[u8]) {
+    let length = min(ram.len(), data.len());
+    ram[..length].copy_from_slice(&data[..length]);
 }
 
-pub fn from(cartridge: Cartridge) -> Box<dyn MBC> {
-    match cartridge.cartridge_type() {
+pub fn from(cartridge: Cartridge) -> Result<Box<dyn MBC>, EmulatorError> {
+
+    // Multicarts are recognised when the cartridge is loaded, since their header can't be trusted
+    match cartridge.kind().mapper {
+        Mapper::MMM01 => return Ok(Box::new(mmm01::MMM01::new(cartridge)?)),
+        Mapper::M161 => return Ok(Box::new(m161::M161::new(cartridge))),
+        Mapper::WisdomTree => return Ok(Box::new(wisdom_tree::WisdomTree::new(cartridge))),
+        _ => ()
+    };
+
+    Ok(match cartridge.cartridge_type() {
         0x00 ..= 0x00 => Box::new(mbc0::MBC0::new(cartridge)),
-        0x01 ..= 0x03 => Box::new(mbc1::MBC1::new(cartridge)),
-        0x05 ..= 0x06 => panic!("MBC2 not implemented!"), // MBC2::new(cartridge),
-        0x0F ..= 0x13 => panic!("MBC3 not implemented!"), // MBC3::new(cartridge),
-        0x19 ..= 0x1E => Box::new(mbc5::MBC5::new(cartridge)),
+        0x01 ..= 0x03 => Box::new(mbc1::MBC1::new(cartridge)?),
+        0x05 ..= 0x06 => Box::new(mbc2::MBC2::new(cartridge)),
+        0x0F ..= 0x13 => Box::new(mbc3::MBC3::new(cartridge)?),
+        0x19 ..= 0x1E => Box::new(mbc5::MBC5::new(cartridge)?),
+        0x22 ..= 0x22 => Box::new(mbc7::MBC7::new(cartridge)),
+        0xFC ..= 0xFC => Box::new(camera::PocketCamera::new(cartridge)?),
+        0xFE ..= 0xFE => Box::new(huc3::HuC3::new(cartridge)?),
+        0xFF ..= 0xFF => Box::new(huc1::HuC1::new(cartridge)?),
 
-        _ => panic!("Unsupported cartridge type: {:#4X}!", cartridge.cartridge_type()),
-    }
+        // Everything else
+        cartridge_type => return Err(EmulatorError::UnsupportedMapper { cartridge_type }),
+    })
 }
@@ -0,0 +1,414 @@
+use std::convert::TryInto;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Cartridge clocks are driven by their own 32.768 KHz crystal; in emulated time that's a second
+//  every 4194304 clock cycles
+pub const CYCLES_PER_SECOND: u64 = 4_194_304;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Size of the clock state appended to a save file, in the layout VBA-M, BGB, SameBoy and others
+///  share: the running then latched registers (0x08 - 0x0C) as 32-bit little-endian words,
+///  followed by the UNIX time the save was made as a 64-bit little-endian word
+pub const FOOTER_SIZE: usize = 48;
+
+/// Older emulators write the timestamp as 32 bits, giving a shorter footer
+pub const SHORT_FOOTER_SIZE: usize = 44;
+
+
+/// What drives a cartridge's real-time clock
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ClockSource {
+
+    /// The clock advances with emulated cycles, so it runs fast or slow (or stops) along with the
+    ///  emulator; deterministic, which is what tests and recordings want
+    Emulated,
+
+    /// The clock follows the host's wall-clock time, as the cartridge would in the real world
+    Host
+}
+
+
+/// Whole seconds passing for a cartridge's clock, from whichever source drives it: counted up from
+/// emulated clock cycles, or taken from the host's time as the clock is used. Shared by the MBC3
+/// and HuC3 clocks, which each count the seconds in their own registers.
+pub struct Timebase {
+    source: ClockSource,
+    cycle_tank: u64,            // Emulated cycles towards the next second
+    last_sync: SystemTime       // Host time the clock was last brought up to date with
+}
+
+
+impl Timebase {
+
+    pub fn new() -> Timebase {
+        Timebase {
+            source: ClockSource::Emulated,
+            cycle_tank: 0,
+            last_sync: SystemTime::now()
+        }
+    }
+
+    /// Switch to a different source; call sync first, so a host clock is caught up before it stops
+    pub fn set_source(&mut self, source: ClockSource) {
+        self.source = source;
+        self.last_sync = SystemTime::now();
+    }
+
+    /// Emulated clock cycles passing, returning how many seconds they complete
+    pub fn run(&mut self, clock_cycles: u64) -> u64 {
+        if self.source != ClockSource::Emulated {
+            return 0;
+        }
+
+        self.cycle_tank += clock_cycles;
+        let seconds = self.cycle_tank / CYCLES_PER_SECOND;
+        self.cycle_tank %= CYCLES_PER_SECOND;
+        seconds
+    }
+
+    /// The whole seconds of host time since the last sync, for a host-driven clock; the fraction
+    ///  carries over to the next sync
+    pub fn sync(&mut self) -> u64 {
+        if self.source != ClockSource::Host {
+            return 0;
+        }
+
+        let elapsed = SystemTime::now().duration_since(self.last_sync).map(|d| d.as_secs()).unwrap_or(0);
+        self.last_sync += Duration::from_secs(elapsed);
+        elapsed
+    }
+
+    /// Start the current second again
+    pub fn restart_second(&mut self) {
+        self.cycle_tank = 0;
+    }
+
+    /// The UNIX time a save's clock state describes. A host clock's registers are only brought
+    ///  up to date as it's used, so they describe the time it was last synced rather than now.
+    pub fn timestamp(&self) -> u64 {
+        let saved_at = match self.source {
+            ClockSource::Emulated => SystemTime::now(),
+            ClockSource::Host => self.last_sync
+        };
+        saved_at.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+    }
+
+    /// Start again from a save's clock state, returning the seconds since the UNIX time it was
+    ///  made, which the clock should be moved forward by
+    pub fn resume(&mut self, timestamp: u64) -> u64 {
+        let now = SystemTime::now();
+        let saved_at = UNIX_EPOCH + Duration::from_secs(timestamp);
+
+        self.cycle_tank = 0;
+        self.last_sync = now;
+        now.duration_since(saved_at).map(|d| d.as_secs()).unwrap_or(0)
+    }
+}
+
+
+/// The MBC3 real-time clock: seconds, minutes, hours and a 9-bit day counter, which a game reads
+/// through a latched copy
+pub struct RTC {
+    seconds: u8,        // 0x08 - 0-59 (6 bits stored)
+    minutes: u8,        // 0x09 - 0-59 (6 bits stored)
+    hours: u8,          // 0x0A - 0-23 (5 bits stored)
+    days: u16,          // 0x0B, and bit 0 of 0x0C - 0-511
+    halted: bool,       // Bit 6 of 0x0C
+    day_carry: bool,    // Bit 7 of 0x0C - set when the day counter overflows, until cleared
+
+    // The copy of the registers a game actually reads, taken by the latch sequence
+    latched: [u8; 5],
+
+    timebase: Timebase
+}
+
+
+impl RTC {
+
+    pub fn new() -> RTC {
+        RTC {
+            seconds: 0,
+            minutes: 0,
+            hours: 0,
+            days: 0,
+            halted: false,
+            day_carry: false,
+
+            latched: [0; 5],
+
+            timebase: Timebase::new()
+        }
+    }
+
+    pub fn set_source(&mut self, source: ClockSource) {
+        self.sync();
+        self.timebase.set_source(source);
+    }
+
+    pub fn run(&mut self, clock_cycles: u64) {
+        if self.halted {
+            return;
+        }
+
+        let seconds = self.timebase.run(clock_cycles);
+        self.advance(seconds);
+    }
+
+    /// Copy the running registers into the latched ones that reads return
+    pub fn latch(&mut self) {
+        self.sync();
+        self.latched = self.registers();
+    }
+
+    /// Read one of the latched registers, 0x08 - 0x0C
+    pub fn read(&self, register: u8) -> u8 {
+        match register {
+            0x08 ..= 0x0C => self.latched[(register - 0x08) as usize],
+            _ => 0xFF
+        }
+    }
+
+    /// Write one of the running registers, 0x08 - 0x0C; the latched copy follows so the value can
+    ///  be read straight back
+    pub fn write(&mut self, register: u8, value: u8) {
+
+        self.sync();
+
+        match register {
+            0x08 => {
+                self.seconds = value & 0x3F;
+
+                // Writing the seconds restarts the current second
+                self.timebase.restart_second();
+            },
+            0x09 => self.minutes = value & 0x3F,
+            0x0A => self.hours = value & 0x1F,
+            0x0B => self.days = (self.days & 0x100) | value as u16,
+            0x0C => {
+                self.days = (self.days & 0xFF) | ((value as u16 & 0x01) << 8);
+                self.halted = value & 0x40 != 0;
+                self.day_carry = value & 0x80 != 0;
+            },
+            _ => return
+        }
+
+        self.latched[(register - 0x08) as usize] = self.registers()[(register - 0x08) as usize];
+    }
+
+    /// Move the clock forward by a number of seconds, as if that much time had passed while running
+    pub fn advance(&mut self, seconds: u64) {
+
+        if self.halted {
+            return;
+        }
+
+        // Registers that have been set out of range tick up to their bit width before wrapping,
+        //  without carrying, so step those one second at a time until everything is in range
+        let mut remaining = seconds;
+        while remaining > 0 && (self.seconds >= 60 || self.minutes >= 60 || self.hours >= 24) {
+            self.tick();
+            remaining -= 1;
+        }
+
+        let total = remaining + self.seconds as u64 + 60 * self.minutes as u64 + 3600 * self.hours as u64
+            + SECONDS_PER_DAY * self.days as u64;
+
+        let days = total / SECONDS_PER_DAY;
+        if days > 0x1FF {
+            self.day_carry = true;
+        }
+
+        self.days = (days & 0x1FF) as u16;
+        self.hours = ((total % SECONDS_PER_DAY) / 3600) as u8;
+        self.minutes = ((total % 3600) / 60) as u8;
+        self.seconds = (total % 60) as u8;
+    }
+
+    /// The clock's state for the end of a save file
+    pub fn footer(&self) -> [u8; FOOTER_SIZE] {
+
+        let mut footer = [0; FOOTER_SIZE];
+        for (i, value) in self.registers().iter().chain(self.latched.iter()).enumerate() {
+            footer[i * 4] = *value;
+        }
+
+        footer[40..48].copy_from_slice(&self.timebase.timestamp().to_le_bytes());
+
+        footer
+    }
+
+    /// Restore the clock from the end of a save file, then move it forward by however long it's
+    ///  been since the save was made, as the real clock would have kept running in the meantime.
+    ///  Returns false, leaving the clock alone, if the footer isn't one of the known sizes.
+    pub fn load_footer(&mut self, footer: &[u8]) -> bool {
+
+        let timestamp = match footer.len() {
+            FOOTER_SIZE => u64::from_le_bytes(footer[40..48].try_into().unwrap()),
+            SHORT_FOOTER_SIZE => u32::from_le_bytes(footer[40..44].try_into().unwrap()) as u64,
+            _ => return false
+        };
+
+        let register = |i: usize| footer[i * 4];
+
+        self.seconds = register(0) & 0x3F;
+        self.minutes = register(1) & 0x3F;
+        self.hours = register(2) & 0x1F;
+        self.days = register(3) as u16 | ((register(4) as u16 & 0x01) << 8);
+        self.halted = register(4) & 0x40 != 0;
+        self.day_carry = register(4) & 0x80 != 0;
+
+        for i in 0..5 {
+            self.latched[i] = register(5 + i);
+        }
+
+        let elapsed = self.timebase.resume(timestamp);
+        self.advance(elapsed);
+
+        true
+    }
+
+    /// The running registers, 0x08 - 0x0C, as a game would see them once latched
+    fn registers(&self) -> [u8; 5] {
+        [
+            self.seconds,
+            self.minutes,
+            self.hours,
+            self.days as u8,
+            ((self.days >> 8) as u8 & 0x01) | if self.halted { 0x40 } else { 0 } | if self.day_carry { 0x80 } else { 0 }
+        ]
+    }
+
+    /// Bring a host-driven clock up to date with the host's time
+    fn sync(&mut self) {
+        let elapsed = self.timebase.sync();
+        self.advance(elapsed);
+    }
+
+    /// A single second passing
+    fn tick(&mut self) {
+
+        self.seconds = (self.seconds + 1) & 0x3F;
+        if self.seconds != 60 {
+            return;
+        }
+        self.seconds = 0;
+
+        self.minutes = (self.minutes + 1) & 0x3F;
+        if self.minutes != 60 {
+            return;
+        }
+        self.minutes = 0;
+
+        self.hours = (self.hours + 1) & 0x1F;
+        if self.hours != 24 {
+            return;
+        }
+        self.hours = 0;
+
+        self.days = (self.days + 1) & 0x1FF;
+        if self.days == 0 {
+            self.day_carry = true;
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use super::{RTC, CYCLES_PER_SECOND, FOOTER_SIZE, SHORT_FOOTER_SIZE};
+
+    #[test]
+    fn counts_and_carries() {
+        let mut rtc = RTC::new();
+        rtc.write(0x08, 59);
+        rtc.write(0x09, 59);
+        rtc.write(0x0A, 23);
+        rtc.write(0x0B, 0xFF);
+        rtc.write(0x0C, 0x01);
+
+        rtc.run(CYCLES_PER_SECOND);
+        rtc.latch();
+
+        assert_eq!([rtc.read(0x08), rtc.read(0x09), rtc.read(0x0A), rtc.read(0x0B)], [0, 0, 0, 0]);
+        assert_eq!(rtc.read(0x0C), 0x80);
+    }
+
+    #[test]
+    fn reads_are_latched() {
+        let mut rtc = RTC::new();
+        rtc.latch();
+        rtc.run(CYCLES_PER_SECOND * 3);
+        assert_eq!(rtc.read(0x08), 0);
+
+        rtc.latch();
+        assert_eq!(rtc.read(0x08), 3);
+    }
+
+    #[test]
+    fn halt_stops_the_clock() {
+        let mut rtc = RTC::new();
+        rtc.write(0x0C, 0x40);
+        rtc.run(CYCLES_PER_SECOND * 10);
+        rtc.advance(100);
+        rtc.latch();
+        assert_eq!(rtc.read(0x08), 0);
+    }
+
+    #[test]
+    fn out_of_range_values_wrap_without_carry() {
+        let mut rtc = RTC::new();
+        rtc.write(0x08, 62);
+        rtc.advance(3);
+        rtc.latch();
+        assert_eq!(rtc.read(0x08), 1);
+        assert_eq!(rtc.read(0x09), 0);
+    }
+
+    #[test]
+    fn advance_matches_ticking() {
+        let mut ticked = RTC::new();
+        let mut advanced = RTC::new();
+        for rtc in [&mut ticked, &mut advanced].iter_mut() {
+            rtc.write(0x0B, 0xFE);
+            rtc.write(0x0C, 0x01);
+        }
+
+        let seconds = 3 * 86400 + 3661;
+        ticked.run(CYCLES_PER_SECOND * seconds);
+        advanced.advance(seconds);
+
+        assert_eq!(ticked.registers(), advanced.registers());
+        assert!(advanced.day_carry);
+    }
+
+    #[test]
+    fn footer_fast_forwards() {
+        let mut rtc = RTC::new();
+        rtc.write(0x0A, 5);
+        rtc.latch();
+
+        // Pretend the save was made an hour and a half ago
+        let mut footer = rtc.footer();
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        footer[40..48].copy_from_slice(&(now - 5400).to_le_bytes());
+
+        let mut restored = RTC::new();
+        assert!(restored.load_footer(&footer));
+        assert_eq!(restored.read(0x0A), 5);
+
+        restored.latch();
+        assert_eq!([restored.read(0x0A), restored.read(0x09)], [6, 30]);
+
+        // The older 44-byte layout
+        let mut short = [0; SHORT_FOOTER_SIZE];
+        short.copy_from_slice(&footer[..SHORT_FOOTER_SIZE]);
+        short[40..44].copy_from_slice(&((now - 5400) as u32).to_le_bytes());
+        assert!(RTC::new().load_footer(&short));
+
+        assert!(!RTC::new().load_footer(&footer[..FOOTER_SIZE - 1]));
+    }
+}
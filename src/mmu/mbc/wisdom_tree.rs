@@ -0,0 +1,64 @@
+use crate::cartridge::Cartridge;
+use super::MBC;
+
+// Wisdom Tree cartridges switch the whole 32 KiB address space at once
+const BANK_SIZE: usize = 0x8000;
+
+
+/// The unlicensed Wisdom Tree mapper: a write anywhere in 0x0000 - 0x3FFF selects a 32 KiB bank by
+/// the low bits of the address written to, ignoring the value
+pub struct WisdomTree {
+    cartridge: Cartridge,
+    bank: u8
+}
+
+
+impl WisdomTree {
+    pub fn new(cartridge: Cartridge) -> WisdomTree {
+        WisdomTree {
+            cartridge,
+            bank: 0
+        }
+    }
+}
+
+
+impl MBC for WisdomTree {
+
+    fn read(&mut self, address: u16) -> u8 {
+        match address {
+            0x0000..=0x7FFF => {
+                let address = (BANK_SIZE * self.bank as usize) | address as usize;
+                self.cartridge.rom[address % self.cartridge.rom.len()]
+            },
+            _ => 0xFF
+        }
+    }
+
+    fn write(&mut self, address: u16, _value: u8) {
+        if let 0x0000..=0x3FFF = address {
+            self.bank = (address & 0x3F) as u8;
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+
+    use crate::cartridge::Cartridge;
+    use crate::testing::rom;
+    use crate::traits::MBC;
+    use super::WisdomTree;
+
+    #[test]
+    fn bank_from_address() {
+        let mut data = rom(0x00, 0x00, 0x00, &[]);
+        data.resize(0x20000, 0);
+        data[0x18000] = 0x03;
+
+        let mut mbc = WisdomTree::new(Cartridge::parse(data).unwrap());
+        mbc.write(0x0003, 0x00);
+        assert_eq!(mbc.read(0x0000), 0x03);
+    }
+}
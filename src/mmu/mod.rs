@@ -1,21 +1,33 @@
-mod mbc;
+pub mod boot;
+mod hdma;
+pub mod mbc;
+mod oam_dma;
 
 use std::fmt;
 
+use crate::cartridge::metadata::CgbSupport;
+use crate::error::EmulatorError;
+use crate::mmu::boot::BootRom;
+use crate::mmu::hdma::{Hdma, BLOCK_CYCLES, BLOCK_SIZE};
+use crate::mmu::mbc::camera::SensorImage;
+use crate::mmu::mbc::rtc::ClockSource;
+use crate::mmu::oam_dma::OamDma;
+use crate::model::Model;
 use crate::traits::{MBC, MemoryMap, RunComponent};
 
 use super::cartridge::Cartridge;
 use super::ppu::PPU;
 use super::sound::Sound;
 use super::timer::Timer;
-use super::joypad::Joypad;
+use super::joypad::{Buttons, Joypad, Tilt};
 use super::serial::Serial;
 
-const W_RAM_SIZE: usize = 0x2000;
+const W_RAM_BANK_SIZE: usize = 0x1000;
+const W_RAM_SIZE: usize = 8 * W_RAM_BANK_SIZE;    // Banks 2-7 only exist on a CGB
 const H_RAM_SIZE: usize = 0x7F;
 
 
-const INITIAL_MEMORY_CONTENTS: &'static [(u16, u8); 31] = &[
+const INITIAL_MEMORY_CONTENTS: &[(u16, u8); 31] = &[
 
     // Timer
     (0xFF05, 0x00), (0xFF06, 0x00), (0xFF07, 0x00),
@@ -39,9 +51,19 @@ const INITIAL_MEMORY_CONTENTS: &'static [(u16, u8); 31] = &[
 
 pub struct MMU {
 
-    in_bios: bool,
+    model: Model,
+    cgb_mode: bool,     // CGB features are in use; false for DMG games on a CGB
+
+    // 0xFF4D : KEY1 : the CPU, timer and serial run at twice the clock of everything else while
+    //  in double speed, switched by a STOP once prepared (CGB mode only)
+    double_speed: bool,
+    speed_switch_prepared: bool,
+
+    // Mapped over the cartridge until 0xFF50 is written, then dropped
+    boot_rom: Option<BootRom>,
 
     w_ram: [u8; W_RAM_SIZE],
+    w_ram_bank: u8,     // 0xFF70 : SVBK : bank at 0xD000 - 0xDFFF (CGB only), where 0 means 1
     h_ram: [u8; H_RAM_SIZE],
 
     mbc: Box<dyn MBC>,
@@ -51,6 +73,10 @@ pub struct MMU {
     joypad: Joypad,
     serial: Serial,
 
+    oam_dma: OamDma,    // 0xFF46 : OAM DMA
+    hdma: Hdma,         // 0xFF51 - 0xFF55 : VRAM DMA (CGB mode only)
+    dma_cycles: u64,    // Clock cycles the CPU has been held for by VRAM DMA copies, to catch up
+
     // Corresponds to the IE (Interrupt Enable R/W) Register at 0xFFFF
     interrupt_enable: u8
 }
@@ -58,35 +84,145 @@ pub struct MMU {
 
 impl MMU {
 
-    pub fn new(cartridge: Cartridge) -> MMU {
+    /// Create the MMU with a cartridge inserted. With a boot ROM, memory is left as at power on
+    ///  for the boot ROM to set up; without one, it's set to what the boot ROM would leave.
+    pub fn new(cartridge: Cartridge, model: Model, boot_rom: Option<BootRom>) -> Result<MMU, EmulatorError> {
 
-        let sdl_context = sdl2::init().unwrap();
-        let video_subsystem = sdl_context.video().unwrap();
-
-        let event_pump = sdl_context.event_pump().unwrap();
+        let cgb_game = cartridge.cgb_support() != CgbSupport::None;
 
         let mut mmu = MMU {
-            in_bios: false,
+            model,
+            cgb_mode: model.is_cgb(),
+            double_speed: false,
+            speed_switch_prepared: false,
+            boot_rom,
 
             w_ram: [0; W_RAM_SIZE],
+            w_ram_bank: 0,
             h_ram: [0; H_RAM_SIZE],
 
-            mbc: mbc::from(cartridge),
-            ppu: PPU::new(video_subsystem),
-            apu: Sound::new(),
+            mbc: mbc::from(cartridge)?,
+            ppu: PPU::new(model),
+            apu: Sound::new(model),
             timer: Timer::new(),
-            joypad: Joypad::new(event_pump),
+            joypad: Joypad::new(),
             serial: Serial::new(),
 
+            oam_dma: OamDma::new(),
+            hdma: Hdma::new(),
+            dma_cycles: 0,
+
             interrupt_enable: 0
         };
 
-        // Initialize memory contents to post-BIOS values
-        for (address, value) in INITIAL_MEMORY_CONTENTS.iter() {
-            mmu.write(*address, *value);
+        // Initialize memory contents to post-BIOS values, including the mode a CGB boot ROM
+        //  would choose, and the palettes it would set up for a DMG game
+        if mmu.boot_rom.is_none() {
+            for (address, value) in INITIAL_MEMORY_CONTENTS.iter() {
+                mmu.write(*address, *value);
+            }
+
+            mmu.set_cgb_mode(cgb_game);
+            if model.is_cgb() && !cgb_game {
+                mmu.ppu.set_dmg_greys();
+            }
+        }
+
+        Ok(mmu)
+    }
+
+    /*************************/
+    /*    Frontend Access    */
+    /*************************/
+
+    /// The model of GameBoy the MMU belongs to
+    pub fn model(&self) -> Model {
+        self.model
+    }
+
+    /// Whether CGB features are in use: on a CGB, unless running a DMG game in compatibility mode
+    pub fn cgb_mode(&self) -> bool {
+        self.cgb_mode
+    }
+
+    /// Switch between CGB mode and DMG compatibility mode; the boot ROM does this through KEY0
+    fn set_cgb_mode(&mut self, cgb_mode: bool) {
+        self.cgb_mode = cgb_mode && self.model.is_cgb();
+        self.ppu.set_cgb_mode(self.cgb_mode);
+        if !self.cgb_mode {
+            self.w_ram_bank = 0;
+        }
+    }
+
+    /// Whether the CPU is running at double speed (CGB mode only)
+    pub fn double_speed(&self) -> bool {
+        self.double_speed
+    }
+
+    /// Called on a STOP: switch speed if a switch has been prepared through KEY1, returning
+    ///  whether the speed switched
+    pub fn switch_speed(&mut self) -> bool {
+        let switch = self.cgb_mode && self.speed_switch_prepared;
+        if switch {
+            self.double_speed = !self.double_speed;
+            self.speed_switch_prepared = false;
         }
+        switch
+    }
+
+    /// Take the clock cycles the CPU has been held for by VRAM DMA since the last call
+    pub fn take_dma_cycles(&mut self) -> u64 {
+        std::mem::replace(&mut self.dma_cycles, 0)
+    }
 
-        mmu
+    /// The most recently drawn frame, as RGB24 bytes in row-major order
+    pub fn framebuffer(&self) -> &[u8] {
+        self.ppu.framebuffer()
+    }
+
+    /// Returns whether the PPU has completed a frame since the last call
+    pub fn take_frame_ready(&mut self) -> bool {
+        self.ppu.take_frame_ready()
+    }
+
+    /// Take the audio samples produced since the last call, as interleaved stereo (L, R) pairs
+    pub fn take_audio_samples(&mut self) -> Vec<i16> {
+        self.apu.take_samples()
+    }
+
+    /// Update the buttons currently held down
+    pub fn set_buttons(&mut self, buttons: Buttons) {
+        self.joypad.set_buttons(buttons);
+    }
+
+    /// Choose what drives the cartridge's real-time clock, if it has one
+    pub fn set_clock_source(&mut self, source: ClockSource) {
+        self.mbc.set_clock_source(source);
+    }
+
+    /// Update how the GameBoy is tilted, for cartridges with an accelerometer
+    pub fn set_tilt(&mut self, tilt: Tilt) {
+        self.mbc.set_tilt(tilt);
+    }
+
+    /// Change what the cartridge's camera sees, if it has one
+    pub fn set_sensor_image(&mut self, image: SensorImage) {
+        self.mbc.set_sensor_image(image);
+    }
+
+    /// Whether the cartridge's rumble motor has run since last asked
+    pub fn take_rumble(&mut self) -> bool {
+        self.mbc.take_rumble()
+    }
+
+    /// The cartridge's battery-backed save data, if it has any
+    pub fn save_data(&self) -> Option<Vec<u8>> {
+        self.mbc.save_data()
+    }
+
+    /// Restore the cartridge's battery-backed save data
+    pub fn load_save_data(&mut self, data: &[u8]) {
+        self.mbc.load_save_data(data);
     }
 
     /*************************/
@@ -106,24 +242,142 @@ impl MMU {
         self.write(address+1, upper);
     }
 
+    /*************************/
+    /*          Bus          */
+    /*************************/
+
+    /// Read from the bus, as a DMA does; the CPU's reads go through `read`
+    #[allow(unreachable_patterns)]
+    fn read_bus(&mut self, address: u16) -> u8 {
+
+        if let Some(value) = self.read_boot_rom(address) {
+            return value;
+        }
+
+        match address {
+            0x0000 ..= 0x3FFF => self.mbc.read(address),                    // ROM
+            0x4000 ..= 0x7FFF => self.mbc.read(address),                    // Switchable ROM Bank
+            0x8000 ..= 0x9FFF => self.ppu.read(address),                    // Video RAM
+            0xA000 ..= 0xBFFF => self.mbc.read(address),                    // Switchable RAM Bank
+            0xC000 ..= 0xDFFF => self.read_ram(address),                    // Internal RAM
+            0xE000 ..= 0xFDFF => self.read_echo(address),                   // Echo RAM
+            0xFE00 ..= 0xFE9F => self.ppu.read(address),                    // Sprite Attributes
+            0xFEA0 ..= 0xFEFF => 0xFF,                                      // Unusable
+
+            0xFF00 ..= 0xFF7F => match address {                            // IO Registers
+                0xFF00 ..= 0xFF00 => self.joypad.read(),
+                0xFF01 ..= 0xFF02 => self.serial.read(address),
+                0xFF03 ..= 0xFF03 => 0xFF,                                  // unmapped
+                0xFF04 ..= 0xFF07 => self.timer.read(address),
+                0xFF08 ..= 0xFF0E => 0xFF,                                  // unmapped
+                0xFF0F ..= 0xFF0F => self.interrupt_flag_read(),
+                0xFF10 ..= 0xFF14 => self.apu.read(address),
+                0xFF15 ..= 0xFF15 => 0xFF,                                  // unmapped
+                0xFF16 ..= 0xFF1E => self.apu.read(address),
+                0xFF1F ..= 0xFF1F => 0xFF,                                  // unmapped
+                0xFF20 ..= 0xFF26 => self.apu.read(address),
+                0xFF27 ..= 0xFF2F => 0xFF,                                  // unmapped
+                0xFF30 ..= 0xFF3F => self.apu.read(address),
+                0xFF46 ..= 0xFF46 => self.oam_dma.read(),
+                0xFF40 ..= 0xFF4B => self.ppu.read(address),
+                0xFF4C ..= 0xFF4C => 0xFF,                                  // unmapped
+                0xFF4D ..= 0xFF4D if self.cgb_mode => {                     // CGB Only - KEY1 - Speed Switch
+                    (self.double_speed as u8) << 7 | 0x7E | self.speed_switch_prepared as u8
+                },
+                0xFF4D ..= 0xFF4D => 0xFF,
+                0xFF4E ..= 0xFF4E => 0xFF,                                  // unmapped
+                0xFF4F ..= 0xFF4F => self.ppu.read(address),
+                0xFF50 ..= 0xFF50 => 0xFE | self.boot_rom.is_none() as u8,  // Boot ROM Disable
+                0xFF51 ..= 0xFF55 if self.cgb_mode => self.hdma.read(address), // CGB Only - HDMA1-5 - VRAM DMA
+                0xFF51 ..= 0xFF55 => 0xFF,
+                0xFF56 ..= 0xFF56 => 0xFF,    // CGB Only - RP - Infrared Comm. Port
+                0xFF57 ..= 0xFF67 => 0xFF,                                  // unmapped
+                0xFF68 ..= 0xFF6B => self.ppu.read(address),
+                0xFF6C ..= 0xFF6F => 0xFF,                                  // unmapped
+                0xFF70 ..= 0xFF70 if self.cgb_mode => 0xF8 | self.w_ram_bank,   // CGB Only - SVBK - WRAM Bank
+                0xFF70 ..= 0xFF70 => 0xFF,
+                0xFF71 ..= 0xFF7F => 0xFF,                                  // unmapped
+
+                _ => panic!("unmapped io register address {:#06X}", address)
+            },
+
+            0xFF80 ..= 0xFFFE => self.read_hram(address),                   // High RAM
+            0xFFFF ..= 0xFFFF => self.interrupt_enable,                     // Interrupt Register
+
+            _ => panic!("Unmapped address {:#06X}", address)
+        }
+    }
+
+    /*************************/
+    /*        VRAM DMA       */
+    /*************************/
+
+    /// Copy every block of a general-purpose DMA straight away, if one has just been started
+    fn general_dma(&mut self) {
+        if let Some(blocks) = self.hdma.take_general() {
+            for _ in 0..blocks {
+                self.copy_dma_block();
+            }
+        }
+    }
+
+    /// Copy the next block of a VRAM DMA, holding the CPU while it does so; the copy takes the
+    ///  same time whatever the speed, so twice as many CPU cycles in double speed
+    fn copy_dma_block(&mut self) {
+        let (source, destination) = self.hdma.next_block();
+
+        for offset in 0..BLOCK_SIZE {
+            let value = self.read_bus(source.wrapping_add(offset));
+            self.ppu.write(destination + offset, value);
+        }
+
+        self.dma_cycles += BLOCK_CYCLES << self.double_speed as u64;
+    }
+
+    /*************************/
+    /*        Boot ROM       */
+    /*************************/
+
+    /// The boot ROM's byte at an address, while the boot ROM is mapped over it
+    fn read_boot_rom(&self, address: u16) -> Option<u8> {
+        self.boot_rom.as_ref()
+            .filter(|boot_rom| boot_rom.maps(address))
+            .map(|boot_rom| boot_rom.read(address))
+    }
+
+    /// Whether the boot ROM is still mapped; it unmaps itself when it hands over to the game
+    pub fn in_boot_rom(&self) -> bool {
+        self.boot_rom.is_some()
+    }
+
     /*************************/
     /*  RAM (0xC000-0xFDFF)  */
     /*************************/
 
+    /// Where an address in 0xC000 - 0xDFFF falls in WRAM: bank 0, then the switchable bank
+    fn w_ram_address(&self, address: u16) -> usize {
+        let bank = match address & 0x1000 {
+            0 => 0,
+            _ => std::cmp::max(1, self.w_ram_bank) as usize
+        };
+        bank * W_RAM_BANK_SIZE + (address as usize & (W_RAM_BANK_SIZE - 1))
+    }
+
     fn read_ram(&mut self, address: u16) -> u8 {
-        self.w_ram[(address as usize) & (W_RAM_SIZE - 1)]
+        self.w_ram[self.w_ram_address(address)]
     }
 
     fn read_echo(&mut self, address: u16) -> u8 {
-        self.read_ram(address & 0xDDFF)
+        self.read_ram(address - 0x2000)
     }
 
     fn write_ram(&mut self, address: u16, value: u8) {
-        self.w_ram[(address as usize) & (W_RAM_SIZE - 1)] = value;
+        let address = self.w_ram_address(address);
+        self.w_ram[address] = value;
     }
 
     fn write_echo(&mut self, address: u16, value: u8) {
-        self.write_ram(address & 0xDDFF, value);
+        self.write_ram(address - 0x2000, value);
     }
 
     /*************************/
@@ -168,67 +422,55 @@ impl MMU {
 impl RunComponent for MMU {
 
     fn run(&mut self, cpu_cycles: u64) {
-        self.ppu.run_for(cpu_cycles);
+
+        // OAM DMA copies a byte every M-cycle of the CPU, which is 16 of the cycles given here
+        for _ in 0..cpu_cycles / 16 {
+            if let Some((source, offset)) = self.oam_dma.tick() {
+                let value = self.read_bus(source);
+                self.ppu.write_oam(offset, value);
+            }
+        }
+
+        // In double speed the timer and serial keep pace with the CPU, while everything else
+        //  stays on the normal clock, so sees half as many cycles
+        let cycles = match self.double_speed {
+            true => cpu_cycles / 2,
+            false => cpu_cycles
+        };
+
+        self.ppu.run_for(cycles);
+        if self.ppu.take_hblank() && self.hdma.hblank_pending() {
+            self.copy_dma_block();
+        }
         self.timer.run(cpu_cycles / 4);
-        self.joypad.run(cpu_cycles);
+        self.serial.run(cpu_cycles / 4);
+        self.apu.run(cycles / 4);
+        self.joypad.run(cycles);
+
+        // The cartridge counts clock cycles, like the timer, not the scaled cycles given here
+        self.mbc.run(cycles / 4);
     }
 }
 
 
 impl MemoryMap for MMU {
 
-    #[allow(unreachable_patterns)]
     fn read(&mut self, address: u16) -> u8 {
-        match address {
-            0x0000 ..= 0x3FFF => self.mbc.read(address),                    // ROM
-            0x4000 ..= 0x7FFF => self.mbc.read(address),                    // Switchable ROM Bank
-            0x8000 ..= 0x9FFF => self.ppu.read(address),                    // Video RAM
-            0xA000 ..= 0xBFFF => self.mbc.read(address),                    // Switchable RAM Bank
-            0xC000 ..= 0xDFFF => self.read_ram(address),                    // Internal RAM
-            0xE000 ..= 0xFDFF => self.read_echo(address),                   // Echo RAM
-            0xFE00 ..= 0xFE9F => self.ppu.read(address),                    // Sprite Attributes
-            0xFEA0 ..= 0xFEFF => 0xFF,                                      // Unusable
-
-            0xFF00 ..= 0xFF7F => match address {                            // IO Registers
-                0xFF00 ..= 0xFF00 => self.joypad.read(),
-                0xFF01 ..= 0xFF02 => self.serial.read(address),
-                0xFF03 ..= 0xFF03 => 0xFF,                                  // unmapped
-                0xFF04 ..= 0xFF07 => self.timer.read(address),
-                0xFF08 ..= 0xFF0E => 0xFF,                                  // unmapped
-                0xFF0F ..= 0xFF0F => self.interrupt_flag_read(),
-                0xFF10 ..= 0xFF14 => self.apu.read(address),
-                0xFF15 ..= 0xFF15 => 0xFF,                                  // unmapped
-                0xFF16 ..= 0xFF1E => self.apu.read(address),
-                0xFF1F ..= 0xFF1F => 0xFF,                                  // unmapped
-                0xFF20 ..= 0xFF26 => self.apu.read(address),
-                0xFF27 ..= 0xFF2F => 0xFF,                                  // unmapped
-                0xFF30 ..= 0xFF3F => self.apu.read(address),
-                0xFF40 ..= 0xFF4B => self.ppu.read(address),
-                0xFF4C ..= 0xFF4C => 0xFF,                                  // unmapped
-                0xFF4D ..= 0xFF4D => self.ppu.read(address),
-                0xFF4E ..= 0xFF4E => 0xFF,                                  // unmapped
-                0xFF4F ..= 0xFF4F => self.ppu.read(address),
-                0xFF50 ..= 0xFF50 => if self.in_bios { 1 } else { 0 },
-                0xFF51 ..= 0xFF55 => self.ppu.read(address),
-                0xFF56 ..= 0xFF56 => 0xFF,    // CGB Only - RP - Infrared Comm. Port
-                0xFF57 ..= 0xFF67 => 0xFF,                                  // unmapped
-                0xFF68 ..= 0xFF6B => self.ppu.read(address),
-                0xFF6C ..= 0xFF6F => 0xFF,                                  // unmapped
-                0xFF70 ..= 0xFF70 => 0xFF,    // CGB Only - SVBK - WRAM Bank
-                0xFF71 ..= 0xFF7F => 0xFF,                                  // unmapped
-
-                _ => panic!("unmapped io register address {:#06X}", address)
-            },
-
-            0xFF80 ..= 0xFFFE => self.read_hram(address),                   // High RAM
-            0xFFFF ..= 0xFFFF => self.interrupt_enable,                     // Interrupt Register
 
-            _ => panic!("Unmapped address {:#06X}", address)
+        // The CPU can only reach the IO registers and HRAM while an OAM DMA is copying
+        match self.oam_dma.active() && address < 0xFF00 {
+            true => 0xFF,
+            false => self.read_bus(address)
         }
     }
 
     #[allow(unreachable_patterns)]
     fn write(&mut self, address: u16, value: u8) {
+
+        if self.oam_dma.active() && address < 0xFF00 {
+            return;
+        }
+
         match address {
             0x0000 ..= 0x3FFF => self.mbc.write(address, value),            // ROM
             0x4000 ..= 0x7FFF => self.mbc.write(address, value),            // Switchable ROM Bank
@@ -253,17 +495,25 @@ impl MemoryMap for MMU {
                 0xFF20 ..= 0xFF26 => self.apu.write(address, value),
                 0xFF27 ..= 0xFF2F => (),                                            // unmapped
                 0xFF30 ..= 0xFF3F => self.apu.write(address, value),
+                0xFF46 ..= 0xFF46 => self.oam_dma.write(value),
                 0xFF40 ..= 0xFF4B => self.ppu.write(address, value),
+                0xFF4C ..= 0xFF4C if self.boot_rom.is_some() => self.set_cgb_mode(value & 0x04 == 0), // KEY0 - CGB Boot ROM Only
                 0xFF4C ..= 0xFF4C => (),                                            // unmapped
-                0xFF4D ..= 0xFF4D => self.ppu.write(address, value),
+                0xFF4D ..= 0xFF4D if self.cgb_mode => self.speed_switch_prepared = value & 0x01 != 0,
+                0xFF4D ..= 0xFF4D => (),    // CGB Only - KEY1 - Speed Switch
                 0xFF4E ..= 0xFF4E => (),                                            // unmapped
                 0xFF4F ..= 0xFF4F => self.ppu.write(address, value),
-                0xFF50 ..= 0xFF50 => self.in_bios = self.in_bios && value != 0,
-                0xFF51 ..= 0xFF55 => self.ppu.write(address, value),
+                0xFF50 ..= 0xFF50 => if value != 0 { self.boot_rom = None },     // Boot ROM Disable
+                0xFF51 ..= 0xFF55 if self.cgb_mode => {
+                    self.hdma.write(address, value);
+                    self.general_dma();
+                },
+                0xFF51 ..= 0xFF55 => (),    // CGB Only - HDMA1-5 - VRAM DMA
                 0xFF56 ..= 0xFF56 => (),    // CGB Only - RP - Infrared Comm. Port
                 0xFF57 ..= 0xFF67 => (),                                            // unmapped
                 0xFF68 ..= 0xFF6B => self.ppu.write(address, value),
                 0xFF6C ..= 0xFF6F => (),                                            // unmapped
+                0xFF70 ..= 0xFF70 if self.cgb_mode => self.w_ram_bank = value & 0x07,
                 0xFF70 ..= 0xFF70 => (),    // CGB Only - SVBK - WRAM Bank
                 0xFF71 ..= 0xFF7F => (),                                            // unmapped
 
@@ -283,3 +533,112 @@ impl fmt::Debug for MMU {
         write!(f, "MMU Debug")
     }
 }
+
+
+#[cfg(test)]
+mod test {
+
+    use crate::cartridge::Cartridge;
+    use crate::mmu::MMU;
+    use crate::model::Model;
+    use crate::testing::{mooneye_all, rom};
+    use crate::traits::{MemoryMap, RunComponent};
+
+    fn mmu(model: Model, cgb_flag: u8) -> MMU {
+        let mut data = rom(0x00, 0x00, 0x00, &[]);
+        data[0x0143] = cgb_flag;
+        MMU::new(Cartridge::parse(data).unwrap(), model, None).unwrap()
+    }
+
+    #[test]
+    fn cgb_banking() {
+        let mut mmu = mmu(Model::CGB, 0x80);
+        assert!(mmu.cgb_mode());
+
+        // WRAM bank 0 selects bank 1
+        for bank in 0..8 {
+            mmu.write(0xFF70, bank);
+            mmu.write(0xD000, 0x10 | bank);
+        }
+        mmu.write(0xFF70, 0x00);
+        assert_eq!(mmu.read(0xD000), 0x11);
+        assert_eq!(mmu.read(0xFF70), 0xF8);
+        mmu.write(0xFF70, 0x07);
+        assert_eq!(mmu.read(0xD000), 0x17);
+        assert_eq!(mmu.read(0xF000), 0x17);
+
+        mmu.write(0xFF4F, 0x01);
+        mmu.write(0x8000, 0xAB);
+        mmu.write(0xFF4F, 0x00);
+        assert_eq!(mmu.read(0x8000), 0x00);
+        assert_eq!(mmu.read(0xFF4F), 0xFE);
+    }
+
+    #[test]
+    fn oam_dma() {
+        let mut mmu = mmu(Model::DMG, 0x00);
+
+        // With the LCD off, OAM can be read back whatever the PPU would be doing
+        mmu.write(0xFF40, 0x00);
+
+        for offset in 0..0xA0 {
+            mmu.write(0xC000 + offset, offset as u8);
+        }
+        mmu.write(0xFF80, 0x42);
+        mmu.write(0xFF46, 0xC0);
+        assert_eq!(mmu.read(0xFF46), 0xC0);
+
+        // Starting, then a byte copied; only IO and HRAM can be reached meanwhile
+        mmu.run(32);
+        assert_eq!(mmu.read(0xC001), 0xFF);
+        assert_eq!(mmu.read(0xFE00), 0xFF);
+        assert_eq!(mmu.read(0xFF80), 0x42);
+        mmu.write(0xC001, 0x00);
+
+        mmu.run(159 * 16);
+        assert_eq!(mmu.read(0xC001), 0x01);
+        assert_eq!(mmu.ppu.read(0xFE00), 0x00);
+        assert_eq!(mmu.ppu.read(0xFE9F), 0x9F);
+    }
+
+    #[test]
+    fn general_dma() {
+        let mut mmu = mmu(Model::CGB, 0x80);
+
+        for offset in 0..0x20 {
+            mmu.write(0xC100 + offset, offset as u8 + 1);
+        }
+
+        // Two blocks from 0xC100 to 0x8800
+        mmu.write(0xFF51, 0xC1);
+        mmu.write(0xFF52, 0x00);
+        mmu.write(0xFF53, 0x08);
+        mmu.write(0xFF54, 0x00);
+        mmu.write(0xFF55, 0x01);
+
+        assert_eq!(mmu.read(0x8800), 0x01);
+        assert_eq!(mmu.read(0x881F), 0x20);
+        assert_eq!(mmu.read(0x8820), 0x00);
+        assert_eq!(mmu.read(0xFF55), 0xFF);
+        assert_eq!(mmu.take_dma_cycles(), 64);
+        assert_eq!(mmu.take_dma_cycles(), 0);
+    }
+
+    #[test]
+    fn dmg_game_on_cgb() {
+        let mut mmu = mmu(Model::CGB, 0x00);
+        assert!(!mmu.cgb_mode());
+
+        mmu.write(0xFF70, 0x02);
+        mmu.write(0xD000, 0x42);
+        mmu.write(0xFF70, 0x03);
+        assert_eq!(mmu.read(0xD000), 0x42);
+        assert_eq!(mmu.read(0xFF70), 0xFF);
+        assert_eq!(mmu.read(0xFF68), 0xFF);
+    }
+
+    #[test]
+    fn acceptance_oam_dma() {
+        mooneye_all("acceptance/oam_dma");
+    }
+}
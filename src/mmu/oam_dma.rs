@@ -0,0 +1,94 @@
+/// Bytes copied into OAM by a transfer, one each M-cycle
+const LENGTH: u16 = 0xA0;
+
+
+/// The OAM DMA (0xFF46), which copies 160 bytes from anywhere on the bus into OAM, a byte each
+/// M-cycle, starting the M-cycle after the register is written. While a transfer is copying, the
+/// CPU can only reach the IO registers and HRAM, so the wait routine has to run from HRAM.
+pub struct OamDma {
+    register: u8,           // 0xFF46 : DMA : high byte of the source of the last transfer started
+    source: u16,            // Source of the transfer being copied
+    position: u16,          // Next byte of the transfer to copy; LENGTH when there isn't one
+    starting: Option<u16>   // The source of a transfer starting on the next M-cycle
+}
+
+
+impl OamDma {
+
+    pub fn new() -> OamDma {
+        OamDma {
+            register: 0xFF,
+            source: 0,
+            position: LENGTH,
+            starting: None
+        }
+    }
+
+    pub fn read(&self) -> u8 {
+        self.register
+    }
+
+    /// Start a transfer from `value` * 0x100. Sources from 0xE000 up read the echo of WRAM
+    ///  underneath them. A transfer already copying carries on until the new one starts.
+    pub fn write(&mut self, value: u8) {
+        self.register = value;
+
+        let source = (value as u16) << 8;
+        self.starting = Some(match source {
+            0xE000 ..= 0xFFFF => source - 0x2000,
+            _ => source
+        });
+    }
+
+    /// Whether a transfer is copying, locking the CPU out of everything but IO and HRAM
+    pub fn active(&self) -> bool {
+        self.position < LENGTH
+    }
+
+    /// Run for an M-cycle, returning the bus address of the byte to copy and its offset in OAM
+    pub fn tick(&mut self) -> Option<(u16, u16)> {
+
+        let copy = match self.active() {
+            true => Some((self.source + self.position, self.position)),
+            false => None
+        };
+        self.position += copy.is_some() as u16;
+
+        if let Some(source) = self.starting.take() {
+            self.source = source;
+            self.position = 0;
+        }
+
+        copy
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+
+    use crate::mmu::oam_dma::OamDma;
+
+    #[test]
+    fn transfer() {
+        let mut dma = OamDma::new();
+        assert_eq!(dma.tick(), None);
+
+        // Nothing is copied on the M-cycle the transfer starts
+        dma.write(0xFE);
+        assert_eq!(dma.read(), 0xFE);
+        assert!(!dma.active());
+        assert_eq!(dma.tick(), None);
+        assert!(dma.active());
+
+        assert_eq!(dma.tick(), Some((0xDE00, 0x00)));
+        for offset in 1..0x9F {
+            assert_eq!(dma.tick(), Some((0xDE00 + offset, offset)));
+        }
+
+        // Restarting carries on with the old transfer for an M-cycle
+        dma.write(0x12);
+        assert_eq!(dma.tick(), Some((0xDE9F, 0x9F)));
+        assert_eq!(dma.tick(), Some((0x1200, 0x00)));
+    }
+}
@@ -0,0 +1,96 @@
+use crate::cartridge::Cartridge;
+use crate::cartridge::metadata::{CgbSupport, SgbSupport};
+use crate::mmu::boot::BootRom;
+
+
+/// The GameBoy hardware being emulated. The models differ in small ways that games can see, such
+/// as the register values their boot ROMs leave behind, and which registers exist at all.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Model {
+    DMG0,   // The earliest DMG, with a different boot ROM
+    DMG,    // The original GameBoy
+    MGB,    // GameBoy Pocket
+    SGB,    // Super GameBoy
+    CGB,    // GameBoy Color
+    AGB     // GameBoy Advance, running GameBoy Color games
+}
+
+
+impl Model {
+
+    /// The model a cartridge was made for: a CGB for games with colour support, an SGB for games
+    ///  with Super GameBoy functions, a DMG otherwise
+    pub fn for_cartridge(cartridge: &Cartridge) -> Model {
+        match (cartridge.cgb_support(), cartridge.sgb_support()) {
+            (CgbSupport::Enhanced, _) | (CgbSupport::Only, _) => Model::CGB,
+            (CgbSupport::None, SgbSupport::Supported) => Model::SGB,
+            (CgbSupport::None, SgbSupport::None) => Model::DMG
+        }
+    }
+
+    /// The model to run a boot ROM on: a CGB for a CGB boot ROM, otherwise what the cartridge
+    ///  was made for short of a CGB
+    pub fn for_boot_rom(boot_rom: &BootRom, cartridge: &Cartridge) -> Model {
+        match (boot_rom.is_cgb(), Model::for_cartridge(cartridge)) {
+            (true, _) => Model::CGB,
+            (false, Model::CGB) => Model::DMG,
+            (false, model) => model
+        }
+    }
+
+    /// Look a model up by name, as given on the command line, ignoring case
+    pub fn from_name(name: &str) -> Option<Model> {
+        match name.to_ascii_lowercase().as_str() {
+            "dmg0" => Some(Model::DMG0),
+            "dmg" => Some(Model::DMG),
+            "mgb" => Some(Model::MGB),
+            "sgb" => Some(Model::SGB),
+            "cgb" => Some(Model::CGB),
+            "agb" => Some(Model::AGB),
+            _ => None
+        }
+    }
+
+    /// Whether the model has the GameBoy Color's hardware: its extra registers, banked VRAM and
+    ///  WRAM, and colour palettes
+    pub fn is_cgb(self) -> bool {
+        match self {
+            Model::CGB | Model::AGB => true,
+            Model::DMG0 | Model::DMG | Model::MGB | Model::SGB => false
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+
+    use crate::cartridge::Cartridge;
+    use crate::mmu::boot::BootRom;
+    use crate::model::Model;
+    use crate::testing::rom;
+
+    fn cartridge(cgb_flag: u8, sgb_flag: u8) -> Cartridge {
+        let mut data = rom(0x00, 0x00, 0x00, &[]);
+        data[0x0143] = cgb_flag;
+        data[0x0146] = sgb_flag;
+        data[0x014B] = 0x33;
+        Cartridge::parse(data).unwrap()
+    }
+
+    #[test]
+    fn chosen_from_header() {
+        assert_eq!(Model::for_cartridge(&cartridge(0x00, 0x00)), Model::DMG);
+        assert_eq!(Model::for_cartridge(&cartridge(0x00, 0x03)), Model::SGB);
+        assert_eq!(Model::for_cartridge(&cartridge(0x80, 0x03)), Model::CGB);
+        assert_eq!(Model::for_cartridge(&cartridge(0xC0, 0x00)), Model::CGB);
+
+        let dmg_boot = BootRom::new(vec![0; 0x100]).unwrap();
+        let cgb_boot = BootRom::new(vec![0; 0x900]).unwrap();
+        assert_eq!(Model::for_boot_rom(&dmg_boot, &cartridge(0x80, 0x00)), Model::DMG);
+        assert_eq!(Model::for_boot_rom(&cgb_boot, &cartridge(0x00, 0x00)), Model::CGB);
+
+        assert_eq!(Model::from_name("AGB"), Some(Model::AGB));
+        assert_eq!(Model::from_name("gbc"), None);
+    }
+}
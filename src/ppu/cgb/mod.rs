@@ -0,0 +1,156 @@
+use super::display::Color;
+
+
+/// Bytes of palette RAM for each of the background and objects: 8 palettes of 4 colours, each
+///  colour 2 bytes
+const PALETTE_RAM_SIZE: usize = 64;
+
+/// Greys for the DMG shades (white, light, dark, black) as 15-bit colours, used to colourise DMG
+///  games on a CGB when there's no boot ROM to choose a colourisation
+const DMG_GREYS: [u16; 4] = [0x7FFF, 0x56B5, 0x294A, 0x0000];
+
+
+/// One of the CGB's two banks of colour palettes, for the background or for objects: 64 bytes of
+/// RAM behind an index register (BCPS / OCPS) and a data register (BCPD / OCPD). Colours are
+/// 15-bit little-endian words, 5 bits each of red, green and blue from the low bits up.
+pub struct PaletteMemory {
+    ram: [u8; PALETTE_RAM_SIZE],
+    index: u8,              // Bits 0-5 of the index register
+    auto_increment: bool    // Bit 7 of the index register; move the index on after each write
+}
+
+
+impl PaletteMemory {
+
+    pub fn new() -> PaletteMemory {
+        PaletteMemory {
+            ram: [0; PALETTE_RAM_SIZE],
+            index: 0,
+            auto_increment: false
+        }
+    }
+
+    /// Set up the first two palettes as the greys of a DMG, for DMG games on a CGB
+    pub fn set_dmg_greys(&mut self) {
+        for palette in 0..2 {
+            for (colour, grey) in DMG_GREYS.iter().enumerate() {
+                let offset = palette * 8 + colour * 2;
+                self.ram[offset..offset + 2].copy_from_slice(&grey.to_le_bytes());
+            }
+        }
+    }
+
+    /// BCPS / OCPS read - bit 6 always reads set
+    pub fn read_index(&self) -> u8 {
+        (self.auto_increment as u8) << 7 | 0x40 | self.index
+    }
+
+    pub fn write_index(&mut self, value: u8) {
+        self.auto_increment = value & 0x80 != 0;
+        self.index = value & 0x3F;
+    }
+
+    /// BCPD / OCPD read; the PPU blocks this while it's drawing, when it reads 0xFF
+    pub fn read_data(&self, accessible: bool) -> u8 {
+        match accessible {
+            true => self.ram[self.index as usize],
+            false => 0xFF
+        }
+    }
+
+    /// BCPD / OCPD write; the PPU blocks this while it's drawing, but the index still moves on
+    pub fn write_data(&mut self, value: u8, accessible: bool) {
+        if accessible {
+            self.ram[self.index as usize] = value;
+        }
+        if self.auto_increment {
+            self.index = (self.index + 1) & 0x3F;
+        }
+    }
+
+    /// The colour a pixel of colour number 0-3 takes in one of the 8 palettes
+    pub fn colour(&self, palette: u8, colour: u8) -> Color {
+        let offset = (palette as usize & 0x07) * 8 + (colour as usize & 0x03) * 2;
+        let word = u16::from_le_bytes([self.ram[offset], self.ram[offset + 1]]);
+
+        // Scale each 5-bit component up to 8 bits, so that 0x1F is full brightness
+        let component = |shift: u16| {
+            let value = ((word >> shift) & 0x1F) as u8;
+            value << 3 | value >> 2
+        };
+
+        Color::rgb(component(0), component(5), component(10))
+    }
+}
+
+
+/// The attributes of a background or window tile, from VRAM bank 1 at the same address as the
+///  tile's number in the tile map (CGB only)
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct TileAttributes {
+    pub priority: bool,     // Bit 7 : BG-to-OBJ priority - draw over objects
+    pub flip_y: bool,       // Bit 6 : Vertical mirror
+    pub flip_x: bool,       // Bit 5 : Horizontal mirror
+    pub bank: usize,        // Bit 3 : VRAM bank of the tile's data
+    pub palette: u8         // Bits 0-2 : Background palette
+}
+
+
+impl TileAttributes {
+
+    pub fn from_byte(value: u8) -> TileAttributes {
+        TileAttributes {
+            priority: value & 0x80 != 0,
+            flip_y: value & 0x40 != 0,
+            flip_x: value & 0x20 != 0,
+            bank: ((value & 0x08) >> 3) as usize,
+            palette: value & 0x07
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+
+    use crate::ppu::cgb::{PaletteMemory, TileAttributes};
+    use crate::ppu::display::Color;
+
+    #[test]
+    fn palette_auto_increment() {
+        let mut palettes = PaletteMemory::new();
+
+        // Palette 1, colour 3 : pure red then pure blue
+        palettes.write_index(0x80 | 0x0E);
+        palettes.write_data(0x1F, true);
+        palettes.write_data(0x00, true);
+        assert_eq!(palettes.read_index(), 0xC0 | 0x10);
+
+        palettes.write_data(0x00, true);
+        palettes.write_data(0x7C, true);
+
+        assert_eq!(palettes.colour(1, 3), Color::rgb(0xFF, 0x00, 0x00));
+        assert_eq!(palettes.colour(2, 0), Color::rgb(0x00, 0x00, 0xFF));
+
+        // Blocked writes are dropped, but still move the index on
+        palettes.write_index(0x80 | 0x3F);
+        palettes.write_data(0x12, false);
+        assert_eq!(palettes.read_index(), 0xC0);
+        assert_eq!(palettes.read_data(false), 0xFF);
+        assert_eq!(palettes.read_data(true), 0x00);
+
+        palettes.set_dmg_greys();
+        assert_eq!(palettes.colour(0, 0), Color::rgb(0xFF, 0xFF, 0xFF));
+        assert_eq!(palettes.colour(1, 3), Color::rgb(0x00, 0x00, 0x00));
+    }
+
+    #[test]
+    fn attributes() {
+        let attributes = TileAttributes::from_byte(0b1010_1101);
+        assert!(attributes.priority);
+        assert!(!attributes.flip_y);
+        assert!(attributes.flip_x);
+        assert_eq!(attributes.bank, 1);
+        assert_eq!(attributes.palette, 5);
+    }
+}
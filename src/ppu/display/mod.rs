@@ -1,46 +1,85 @@
-extern crate sdl2;
+use super::{HEIGHT, WIDTH};
 
-// use self::sdl2::Sdl;
-// use self::sdl2::render::WindowCanvas;
-use self::sdl2::rect::{Point};
-use self::sdl2::pixels::Color;
-use self::sdl2::VideoSubsystem;
-use self::sdl2::render::WindowCanvas;
 
-use super::{HEIGHT, WIDTH};
+/// Number of bytes used per pixel in the framebuffer (R, G, B)
+pub const BYTES_PER_PIXEL: usize = 3;
 
-#[allow(unused)]
-pub struct Screen {
-    canvas: WindowCanvas,
+
+/// A pixel position on the GameBoy screen
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32
+}
+
+impl Point {
+    pub fn new(x: i32, y: i32) -> Point {
+        Point { x, y }
+    }
+}
+
+
+/// A 24-bit RGB colour as it will be written into the framebuffer
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8
+}
+
+impl Color {
+    pub fn rgb(r: u8, g: u8, b: u8) -> Color {
+        Color { r, g, b }
+    }
 }
 
-#[allow(unused)]
-impl Screen {
 
-    pub fn new(video_context: VideoSubsystem) -> Screen {
+/// An in-memory representation of the LCD; the PPU draws into this, and any frontend (or test)
+/// can read the finished frame from it without the core knowing how (or if) it gets displayed.
+pub struct Screen {
+    framebuffer: Vec<u8>,       // RGB24, row-major, WIDTH * HEIGHT pixels
+    frame_ready: bool           // Set on entering VBlank, cleared once the frame is taken
+}
 
-        let canvas = video_context
-            .window("Rustboy", WIDTH as u32, HEIGHT as u32)
-            .position_centered().opengl().build().unwrap()
-            .into_canvas().build().unwrap();
+impl Screen {
 
+    pub fn new() -> Screen {
         Screen {
-            canvas
+            framebuffer: vec![0xFF; WIDTH * HEIGHT as usize * BYTES_PER_PIXEL],
+            frame_ready: false
         }
     }
 
     pub fn draw(&mut self, pixels: Vec<(Point, Color)>) {
-        /*
         for (point, color) in pixels.iter() {
-            self.canvas.set_draw_color(*color);
-            self.canvas.draw_point(*point);
+            self.set(*point, *color);
         }
+    }
 
-        self.canvas.present();
+    /// Set a single pixel; anything off-screen is clipped
+    pub fn set(&mut self, point: Point, color: Color) {
+        if point.x < 0 || point.x >= WIDTH as i32 || point.y < 0 || point.y >= HEIGHT as i32 {
+            return;
+        }
 
-         */
+        let offset = (point.y as usize * WIDTH + point.x as usize) * BYTES_PER_PIXEL;
+        self.framebuffer[offset] = color.r;
+        self.framebuffer[offset + 1] = color.g;
+        self.framebuffer[offset + 2] = color.b;
     }
-}
 
+    /// Mark the current frame as complete; called by the PPU on entering VBlank
+    pub fn present(&mut self) {
+        self.frame_ready = true;
+    }
 
+    /// Returns whether a frame was completed since the last call, clearing the flag
+    pub fn take_frame_ready(&mut self) -> bool {
+        std::mem::replace(&mut self.frame_ready, false)
+    }
 
+    /// The most recently drawn frame, as RGB24 bytes in row-major order
+    pub fn framebuffer(&self) -> &[u8] {
+        &self.framebuffer
+    }
+}
@@ -0,0 +1,102 @@
+use super::cgb::TileAttributes;
+
+
+/// A pixel waiting in one of the PPU's FIFOs to be shifted out onto the LCD
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Pixel {
+    pub colour: u8,         // Colour number 0-3; 0 is transparent for objects
+    pub palette: u8,        // Background: CGB palette; objects: OBP0 / OBP1, or CGB palette
+    pub priority: bool,     // Background: CGB BG-to-OBJ priority; objects: drawn behind BG 1-3
+    pub index: u8           // Objects: OAM entry number
+}
+
+
+/// The steps the pixel fetcher goes through for each tile; the first three take 2 dots each,
+///  while pushing is tried every dot until the background FIFO is empty
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FetchStep {
+    TileNumber,
+    DataLow,
+    DataHigh,
+    Push
+}
+
+
+/// The background / window pixel fetcher, which reads a row of a tile at a time from VRAM for
+/// the background FIFO. Each register it depends on is read at the step that uses it, so writes
+/// part way through a line show up from the next tile fetched.
+pub struct Fetcher {
+    pub step: FetchStep,
+    pub dots: u8,           // Dots spent on the current step
+    pub tile_x: u8,         // Tile column, counted from SCX for the background, or the window's edge
+    pub window: bool,       // Fetching window tiles rather than background tiles
+    pub discard_fetch: bool,    // The first fetch of a line is thrown away, delaying the line
+
+    pub tile_number: u8,
+    pub attributes: TileAttributes,
+    pub low: u8,
+    pub high: u8
+}
+
+
+impl Fetcher {
+
+    /// A fetcher at the start of a line's pixel transfer, about to fetch the tile at SCX
+    pub fn new() -> Fetcher {
+        Fetcher {
+            step: FetchStep::TileNumber,
+            dots: 0,
+            tile_x: 0,
+            window: false,
+            discard_fetch: true,
+
+            tile_number: 0,
+            attributes: TileAttributes::default(),
+            low: 0,
+            high: 0
+        }
+    }
+
+    /// Start again from the first step, fetching window tiles from the window's left edge
+    pub fn start_window(&mut self) {
+        self.step = FetchStep::TileNumber;
+        self.dots = 0;
+        self.tile_x = 0;
+        self.window = true;
+    }
+
+    /// Go back to fetching background tiles, from a tile column counted from SCX's
+    pub fn stop_window(&mut self, tile_x: u8) {
+        self.step = FetchStep::TileNumber;
+        self.dots = 0;
+        self.tile_x = tile_x;
+        self.window = false;
+    }
+}
+
+
+/// The colour numbers of a row of 8 pixels from its two bitplanes, left to right
+pub fn tile_row(low: u8, high: u8, flip_x: bool) -> [u8; 8] {
+    let mut row = [0; 8];
+    for (x, colour) in row.iter_mut().enumerate() {
+        let bit = match flip_x {
+            true => x,
+            false => 7 - x
+        };
+        *colour = (high >> bit & 0x01) << 1 | (low >> bit & 0x01);
+    }
+    row
+}
+
+
+#[cfg(test)]
+mod test {
+
+    use crate::ppu::fifo::tile_row;
+
+    #[test]
+    fn tile_rows() {
+        assert_eq!(tile_row(0b1010_0000, 0b1100_0001, false), [3, 2, 1, 0, 0, 0, 0, 2]);
+        assert_eq!(tile_row(0b1010_0000, 0b1100_0001, true), [2, 0, 0, 0, 0, 1, 2, 3]);
+    }
+}
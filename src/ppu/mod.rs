@@ -1,18 +1,21 @@
-mod display;
+pub mod display;
+mod cgb;
+mod fifo;
 mod registers;
 mod oam;
 
-use sdl2::rect::Point;
-use sdl2::pixels::Color;
+use std::collections::VecDeque;
 
 use crate::enums::{Mode, Mode::*};
+use crate::model::Model;
 use crate::traits::{Byte, MemoryMap};
 
+use cgb::{PaletteMemory, TileAttributes};
+use display::{Color, Point};
+use fifo::{Fetcher, FetchStep, Pixel, tile_row};
 use oam::{OAMEntry, OAMFlags};
 use registers::lcdc::LCDC;
 use registers::lcds::LCDS;
-use sdl2::VideoSubsystem;
-use std::cmp::max;
 
 
 const V_RAM_SIZE: usize = 0x2000;
@@ -29,23 +32,41 @@ pub const MAX_SCREEN_Y: u8 = HEIGHT - 1;
 pub const WIDTH: usize = 160;
 
 
-const   CYCLES_PER_LINE: u8 = 114;
 const     V_BLANK_LINES: u8 = 10;
 
 const TOTAL_LINES: u8 = HEIGHT + V_BLANK_LINES;
 
+/// Dots (clock cycles) per line, and spent scanning OAM at the start of each visible line
+const  DOTS_PER_LINE: u16 = 456;
+const OAM_SCAN_DOTS: u16 = 80;
+
+/// Objects the OAM scan can find on one line, and the dots fetching each holds up a line for
+const OBJECTS_PER_LINE: usize = 10;
+const OBJECT_FETCH_DOTS: u8 = 6;
+
+/// The four shades of a DMG, lightest first, as the same greys a CGB shows DMG games in
+const SHADES: [Color; 4] = [
+    Color { r: 0xFF, g: 0xFF, b: 0xFF },
+    Color { r: 0xAD, g: 0xAD, b: 0xAD },
+    Color { r: 0x52, g: 0x52, b: 0x52 },
+    Color { r: 0x00, g: 0x00, b: 0x00 }
+];
+
 
 #[allow(dead_code)]
 pub struct PPU {
 
+    model: Model,
+    cgb_mode: bool,     // CGB features are in use; false for DMG games on a CGB
     display: display::Screen,
 
-    clock: u64,     // Behaves as a counter of how many cycles / ticks have occurred, used to determine
-                    // an appropriate "mode" to switch to at a given point
+    dot: u16,       // Dot within the current line, 0 - 455
+    lcd_starting: bool,     // On the first line after the LCD is turned on, which has no OAM scan
 
     mode: Mode,                 // PPU Mode
-    vram: [u8; V_RAM_SIZE],     // VRAM
-     oam: [u8; OAM_SIZE],       // OAM / Sprite Attribute Table
+    vram: [[u8; V_RAM_SIZE]; 2],    // VRAM; the second bank only exists on a CGB
+    vram_bank: usize,               // 0xFF4F : VBK : VRAM bank the CPU sees (CGB only)
+     oam: [u8; OAM_SIZE],           // OAM / Sprite Attribute Table
 
     lcdc: LCDC,     // 0xFF40 : LCDC Register : LCD C(ontrol) Register
     lcds: LCDS,     // 0xFF41 : LCDS Register : LCD S(tatus) Register
@@ -53,18 +74,37 @@ pub struct PPU {
      scx: u8,       // 0xFF43 : Scroll X
       ly: u8,       // 0xFF44 : LY  (LCD Y)
      lyc: u8,       // 0xFF45 : LYC (LY Compare)
-     dma: u8,       // 0xFF46 : DMA (DMA Transfer and Start Address)
      bgp: u8,       // 0xFF47 : BGP Palette Data (Non-CGB)
     obp0: u8,       // 0xFF48 : Object Palette 0 (Non-CGB)
     obp1: u8,       // 0xFF49 : Object Palette 1 (Non-CGB)
       wy: u8,       // 0xFF4A : Window Y Position
       wx: u8,       // 0xFF4B : Window X Position
 
+    bg_palettes: PaletteMemory,     // 0xFF68 - 0xFF69 : BCPS / BCPD : Background palettes (CGB)
+    obj_palettes: PaletteMemory,    // 0xFF6A - 0xFF6B : OCPS / OCPD : Object palettes (CGB)
+
+    // Pixel transfer state for the current line
+    line_objects: Vec<OAMEntry>,            // Objects found by the OAM scan, not yet fetched
+    fetcher: Fetcher,                       // Background / window tile fetcher
+    bg_fifo: VecDeque<Pixel>,               // Background / window pixels to shift out
+    obj_fifo: VecDeque<Pixel>,              // Object pixels to mix over them
+    object_fetch: Option<(OAMEntry, u8)>,   // Object being fetched, and the dots left to do so
+    lx: u8,                                 // X of the next pixel to shift out onto the LCD
+    discard: u8,                            // Pixels left to throw away for SCX's fine scroll
+    window_active: bool,                    // The window has started on this line
+
+    window_y_triggered: bool,   // LY has matched WY this frame, so the window can start on a line
+    window_line: u8,            // The window's own line counter, which only counts lines it's on
+
+    stat_line: bool,                // The STAT interrupt sources ORed together
+
     pub stat_interrupt: bool,       // A flag to represent a STAT interrupt request; this
                                     // corresponds to bit 1 in the 0xFF0F (Interrupt Flag) register
 
-    pub vblank_interrupt: bool      // A flag to represent a VBlank interrupt request; this flag
+    pub vblank_interrupt: bool,     // A flag to represent a VBlank interrupt request; this flag
                                     // corresponds to bit 0 in the 0xFF0F (Interrupt Flag) register
+
+    hblank_started: bool            // Set on entering HBlank, for an HBlank DMA to copy a block
 }
 
 
@@ -72,13 +112,17 @@ pub struct PPU {
 #[allow(unused)]
 impl PPU {
 
-    pub fn new(video_context: VideoSubsystem) -> PPU {
+    pub fn new(model: Model) -> PPU {
         PPU {
-            clock: 0,
-            display: display::Screen::new(video_context),
+            model,
+            cgb_mode: model.is_cgb(),
+            dot: 0,
+            lcd_starting: false,
+            display: display::Screen::new(),
 
             mode: Mode0,
-            vram: [0; V_RAM_SIZE],
+            vram: [[0; V_RAM_SIZE]; 2],
+            vram_bank: 0,
              oam: [0; OAM_SIZE],
 
             lcdc: LCDC::new(),
@@ -87,33 +131,71 @@ impl PPU {
              scx: 0,
               ly: 0,
              lyc: 0,
-             dma: 0,
              bgp: 0,
             obp0: 0,
             obp1: 0,
               wy: 0,
               wx: 0,
 
+             bg_palettes: PaletteMemory::new(),
+            obj_palettes: PaletteMemory::new(),
+
+            line_objects: Vec::new(),
+            fetcher: Fetcher::new(),
+            bg_fifo: VecDeque::new(),
+            obj_fifo: VecDeque::new(),
+            object_fetch: None,
+            lx: 0,
+            discard: 0,
+            window_active: false,
+
+            window_y_triggered: false,
+            window_line: 0,
+
+            stat_line: false,
+
               stat_interrupt: false,
-            vblank_interrupt: false
+            vblank_interrupt: false,
+
+            hblank_started: false
         }
     }
 
 
-    pub fn dma_transfer(&mut self, value: u8) {
+    /// Switch a CGB between CGB mode and DMG compatibility mode, where VRAM banking and the
+    ///  colour palette registers are locked
+    pub fn set_cgb_mode(&mut self, cgb_mode: bool) {
+        self.cgb_mode = cgb_mode && self.model.is_cgb();
+        if !self.cgb_mode {
+            self.vram_bank = 0;
+        }
+    }
 
-        self.dma = value;
+    /// Colour DMG games on a CGB in plain greys, for when no boot ROM has set up palettes
+    pub fn set_dmg_greys(&mut self) {
+        self.bg_palettes.set_dmg_greys();
+        self.obj_palettes.set_dmg_greys();
+    }
 
-        return;
+    /// The most recently drawn frame, as RGB24 bytes in row-major order
+    pub fn framebuffer(&self) -> &[u8] {
+        self.display.framebuffer()
+    }
 
-        let source = ((value as u16) << 8) * 0x0100;
+    /// Returns whether a full frame has been drawn since the last call
+    pub fn take_frame_ready(&mut self) -> bool {
+        self.display.take_frame_ready()
+    }
+
+    /// Returns whether an HBlank has started since the last call
+    pub fn take_hblank(&mut self) -> bool {
+        std::mem::replace(&mut self.hblank_started, false)
+    }
 
-        for i in 0x00..=0x9F {
-            let read = self.read(source + i as u16);
-            self.write(0xFE00 + i as u16, read);
-        }
 
-        self.clock += 80;
+    /// Write a byte copied into OAM by OAM DMA, which gets through whatever the mode
+    pub fn write_oam(&mut self, offset: u16, value: u8) {
+        self.oam[offset as usize] = value;
     }
 
 
@@ -125,335 +207,473 @@ impl PPU {
             return
         }
 
-        let mut cycles_left = cycles;
-
-        while cycles_left > 0 {
-
-            let current = std::cmp::min(cycles, 80);
-
-            // Any amount of "cycles given" should be cleanly divisible by 4, as the CPU will call
-            //  this function, and should 'scale' the cycles by 4, to correspond with the difference
-            //  between the CPU and PPU in clock speed
-            assert_eq!(current % 4, 0);
-            self.clock += current / 4;
-
-            // One horizontal row is completed, as one horizontal row takes 114 cycles
-            //  1 row = 20 cycles (OAM Search) + ~43 cycles (Pixel Transfer) + ~51 cycles (H-Blank)
-            if self.clock >= CYCLES_PER_LINE as u64 {
-                self.clock %= CYCLES_PER_LINE as u64;
-
-                // Advance to next line (wrapping "around" back to top if necessary)
-                self.ly += 1;
-                self.ly %= HEIGHT + V_BLANK_LINES;
-
-                // If toggled, check for lyc interrupt
-                if self.lcds.lyc_interrupt() && self.ly == self.lyc {
-
-                    // Enable the LCDS Coincidence bit
-                    let value = self.lcds.read() | 0x04;
-                    self.lcds.write(value);
-
-                    self.stat_interrupt = true;
-                }
-            }
+        // The CPU scales the cycles it gives by 4; the PPU draws a dot every clock cycle
+        for _ in 0..cycles / 4 {
+            self.tick();
+        }
+    }
 
+    /// Run for a single dot. Each line takes 456 dots: 80 scanning OAM, then a pixel transfer of
+    ///  at least 172 that grows with fine scrolling, the window and objects, then HBlank for the
+    ///  rest. The 10 lines after the visible 144 are VBlank.
+    fn tick(&mut self) {
 
-            // Compute the mode the PPU 'should' be in, based on whether ly is on the visible area,
-            //  (and if so, whether in OAM / Transfer / HBlank), or in a VBlank area
-            let target_mode = match self.ly {
+        if self.mode == Mode3 {
+            self.transfer_dot();
+        }
 
-                0 ..= MAX_SCREEN_Y => match self.clock {
-                     0 ..= 20 => Mode2,
-                    21 ..= 43 => Mode3,
-                    44 ..= 114 => Mode0,
+        self.dot += 1;
 
-                    _ => panic!("impossible clock value: {}", self.clock)
-                },
+        if (self.mode == Mode2 || self.lcd_starting) && self.dot == OAM_SCAN_DOTS {
+            self.lcd_starting = false;
+            self.enter_mode(Mode3);
+        }
 
-                HEIGHT ..= TOTAL_LINES => Mode1,
+        if self.dot == DOTS_PER_LINE {
+            self.dot = 0;
 
-                _ => panic!("impossible ly value: {}", self.ly)
-            };
+            // Advance to next line (wrapping "around" back to top if necessary)
+            self.ly = (self.ly + 1) % TOTAL_LINES;
 
-            // Check before setting the mode, as interrupts can be generated by switching modes
-            if self.mode != target_mode {
-                self.enter_mode(target_mode);
+            match self.ly {
+                0 ..= MAX_SCREEN_Y => self.enter_mode(Mode2),
+                HEIGHT => self.enter_mode(Mode1),
+                _ => ()
             }
-
-            cycles_left = match cycles_left.checked_sub(current) {
-                Some(i) => i,
-                None => 0
-            };
         }
+
+        self.update_stat();
     }
 
     fn enter_mode(&mut self, mode: Mode) {
 
-        // Compute any byproducts (such as interrupts or line draws) from this mode switch
+        // Compute any byproducts (such as interrupts or line setup) from this mode switch
         match mode {
 
-            // Entering HBlank Mode
+            // Entering HBlank Mode; the line is finished, moving the window down if it was on it
             Mode0 => {
-
-                // Entering HBlank mode indicates pixel transfer is complete, and so the row
-                //  can be drawn
-                self.draw_row();
-
-                if self.lcds.mode_0_h_blank_interrupt() {
-                    self.stat_interrupt = true;
+                self.hblank_started = true;
+                if self.window_active {
+                    self.window_line = self.window_line.wrapping_add(1);
                 }
             }
 
-            // Entering VBlank Mode
+            // Entering VBlank Mode; the window starts again from its top next frame
             Mode1 => {
+                self.display.present();
                 self.vblank_interrupt = true;
-                if self.lcds.mode_1_v_blank_interrupt() {
-                    self.stat_interrupt = true;
-                }
+
+                self.window_y_triggered = false;
+                self.window_line = 0;
             }
 
-            // Entering OAM Search mode
-            Mode2 => if self.lcds.mode_2_oam_interrupt() { self.stat_interrupt = true; }
+            // Entering OAM Search mode; once LY has matched WY, the window can appear on any
+            //  later line of the frame, whatever WY becomes
+            Mode2 => {
+                self.window_y_triggered |= self.ly == self.wy;
+                self.scan_oam();
+            }
 
-            // No interrupts / changes from Pixel Transfer mode
-            Mode3 => ()
+            // Entering Pixel Transfer mode
+            Mode3 => self.start_transfer()
         };
 
         self.mode = mode;
+        self.lcds.set_mode(mode);
     }
 
-    fn draw_row(&mut self) {
-
-        let h = self.lcdc.obj_size().1;
-
-        // 1 - OAM Search
-        let mut visible: Vec<OAMEntry> = Vec::new();
-
-        for entry_number in 0..40 {
-
-            let entry = self.oam_entry(entry_number);
+    /// Update the coincidence flag, and the STAT interrupt line that its sources are ORed onto;
+    ///  an interrupt is only requested as the line rises, so while one source holds it high,
+    ///  another can't request a second. The mode 2 source also fires as line 144 starts, though
+    ///  there's no OAM scan, and the mode 0 source doesn't while the first line after the LCD is
+    ///  turned on waits for its pixel transfer.
+    fn update_stat(&mut self) {
+
+        let coincidence = self.ly == self.lyc;
+        self.lcds.set_coincidence(coincidence);
+
+        let line = (coincidence && self.lcds.lyc_interrupt()) || match self.mode {
+            Mode0 => self.lcds.mode_0_h_blank_interrupt() && !self.lcd_starting,
+            Mode1 => self.lcds.mode_1_v_blank_interrupt()
+                || (self.ly == HEIGHT && self.dot == 0 && self.lcds.mode_2_oam_interrupt()),
+            Mode2 => self.lcds.mode_2_oam_interrupt(),
+            Mode3 => false
+        };
 
-            // Comparison method by which the GameBoy PPU uses to determine whether a given object
-            //  should be considered 'visible' on the current line
-            if entry.x != 0 && self.ly + 16 >= entry.y && self.ly + 16 < entry.y + h {
-                visible.push(entry);
-            }
+        if line && !self.stat_line {
+            self.stat_interrupt = true;
         }
+        self.stat_line = line;
+    }
 
-        // Order based on x position, as the 'first' (from the left) 10 sprites should be shown
-        // TODO - Perhaps it's really just the first 10 from the start of the OAM, no sort?
-        visible.sort_by(|a, b| b.x.cmp(&a.x));
-
-        // GameBoy can only have up to 10 sprites per line, remove (don't draw) anything to the
-        //  'right' of the first 10 sprites, as this is how the hardware will resolve >10 sprites
-        if visible.len() > 10 {
-            visible.drain(10..);
+    /// Write LCDC; turning the LCD off resets LY and leaves the PPU idle in mode 0, and turning
+    ///  it back on starts again from the top of the screen. That first line skips the OAM scan,
+    ///  staying in mode 0 until the pixel transfer starts at the usual dot.
+    fn write_lcdc(&mut self, value: u8) {
+
+        let was_on = self.lcdc.lcd_display_enable();
+        self.lcdc.write(value);
+
+        match (was_on, self.lcdc.lcd_display_enable()) {
+            (true, false) => {
+                self.ly = 0;
+                self.dot = 0;
+                self.lcd_starting = false;
+                self.stat_line = false;
+                self.mode = Mode0;
+                self.lcds.set_mode(Mode0);
+            },
+            (false, true) => {
+                self.lcd_starting = true;
+                self.line_objects.clear();
+                self.window_y_triggered |= self.ly == self.wy;
+                self.update_stat();
+            },
+            _ => ()
         }
+    }
 
-        // 2 - Pixel Transfer
+    /*************************/
+    /*    Pixel Transfer     */
+    /*************************/
 
-        // 2.a - Clear Background
+    /// Mode 2 - find the objects on this line, of which there can be 10
+    fn scan_oam(&mut self) {
 
-        let mut clear_pixels = Vec::new();
-        for i in 0..WIDTH {
-            clear_pixels.push((Point::new(i as u32 as i32, self.ly as u32 as i32), Color::RGB(255, 255, 255)));
-        }
-        // self.display.draw(clear_pixels);
+        let height = self.lcdc.obj_size().1 as u16;
+        let line = self.ly as u16 + 16;
 
-        // let clear_bg = (0..WIDTH).into_iter().map(|x| (Point::new(x as u32 as i32, self.ly as u32 as i32), Color::RGB(0, 0, 0))).collect();
-        //self.display.draw(clear_bg);
+        self.line_objects = (0..40)
+            .map(|entry_number| self.oam_entry(entry_number))
+            .filter(|entry| line >= entry.y as u16 && line < entry.y as u16 + height)
+            .take(OBJECTS_PER_LINE)
+            .collect();
+    }
 
-        // 2.b - Background
-        let background_pixels = self.background_pixels();
-        self.display.draw(background_pixels);
+    /// Mode 3 - set up the fetcher and FIFOs for a new line
+    fn start_transfer(&mut self) {
+        self.fetcher = Fetcher::new();
+        self.bg_fifo.clear();
+        self.obj_fifo.clear();
+        self.object_fetch = None;
 
-        // 2.c - Objects
-        // let object_pixels = self.object_pixels(visible);
-        // self.display.draw(object_pixels);
+        self.lx = 0;
+        self.discard = self.scx & 0x07;
+        self.window_active = false;
+    }
 
+    /// Run the pixel transfer for a dot: the fetcher works towards the next tile, and a pixel is
+    ///  shifted out onto the LCD, unless the window or an object starting here holds it up
+    fn transfer_dot(&mut self) {
 
-        let mut dump = Vec::new();
-        let mut base = 0x8000;
+        // Fetching an object stops everything else until it's merged into the object FIFO
+        if let Some((object, dots)) = self.object_fetch {
+            match dots {
+                1 => {
+                    self.object_fetch = None;
+                    self.merge_object(object);
+                },
+                _ => self.object_fetch = Some((object, dots - 1))
+            }
+            return;
+        }
 
-        for y in 0..18 {
-            for x in 0..20 {
+        // The window starting throws away the background pixels, and fetches its own; with WX
+        //  under 7 it starts off the left of the screen, losing the pixels that are off it
+        if !self.window_active && self.window_starts() {
+            self.window_active = true;
+            self.bg_fifo.clear();
+            self.fetcher.start_window();
+            self.discard = 7_u8.saturating_sub(self.wx);
+        }
 
-                let start = 0x8000 as u16 + (y * 16 * 18) + (x * 16);
-                let dx = (x * 8) as u32 as i32 - 8;
-                let dy = (y * 8) as u32 as i32 - 16;
+        // Turning the window off part way through a line goes back to the background, from
+        //  the first pixel not yet fetched
+        if self.fetcher.window && !self.lcdc.window_display_enable() {
+            let x = (self.scx & 0x07) as usize + self.lx as usize + self.bg_fifo.len();
+            self.fetcher.stop_window((x / 8) as u8);
+        }
 
-                for oy in 0..8 {
+        self.fetch_dot();
 
-                    //println!("{:#06X} {:#06X}", start + (oy * 2), start + (oy * 2) + 1);
+        if self.bg_fifo.is_empty() {
+            return;
+        }
 
-                    let txt1 = self.read(start + (oy * 2));
-                    let txt2 = self.read(start + (oy * 2) + 1);
+        // The first pixels of the line are thrown away for SCX's fine scroll
+        if self.discard > 0 {
+            self.bg_fifo.pop_front();
+            self.discard -= 1;
+            return;
+        }
 
-                    //println!("{:#010b} {:#010b}", txt1, txt2);
+        // An object starting here is fetched once the fetcher has the data of its current tile,
+        //  starting this dot
+        if let Some(index) = self.next_object() {
+            if self.fetcher.step == FetchStep::Push {
+                let object = self.line_objects.remove(index);
+                self.object_fetch = Some((object, OBJECT_FETCH_DOTS - 1));
+            }
+            return;
+        }
 
-                    for ox in 0..8 {
+        self.shift_pixel();
+    }
 
-                        let v0 = if (txt1 & (0x80 >> ox)) != 0 { 1 } else { 0 };
-                        let v1 = if (txt2 & (0x80 >> ox)) != 0 { 1 } else { 0 };
+    /// Run the background / window fetcher for a dot; each read takes 2 dots, while pushing is
+    ///  tried every dot until the background FIFO has emptied
+    fn fetch_dot(&mut self) {
 
-                        let v = v0 << 1 | v1;
+        if self.fetcher.step != FetchStep::Push && self.fetcher.dots == 0 {
+            self.fetcher.dots = 1;
+            return;
+        }
+        self.fetcher.dots = 0;
 
-                        if v == 0 { continue }
+        match self.fetcher.step {
+            FetchStep::TileNumber => {
+                self.fetch_tile_number();
+                self.fetcher.step = FetchStep::DataLow;
+            },
 
-                        let color = match v {
-                            0b00 => Color::RGB(0, 0, 0),
-                            0b01 => Color::RGB(60, 60, 60),
-                            0b10 => Color::RGB(120, 120, 120),
-                            0b11 => Color::RGB(180, 180, 180),
-                            _ => panic!("{}", v)
-                        };
+            FetchStep::DataLow => {
+                self.fetcher.low = self.fetch_tile_data(0);
+                self.fetcher.step = FetchStep::DataHigh;
+            },
 
-                        dump.push((Point::new(dx + ox as u32 as i32,  dy + oy as u32 as i32), color));
+            // The line's first fetch is thrown away, and the same tile fetched again
+            FetchStep::DataHigh => {
+                self.fetcher.high = self.fetch_tile_data(1);
+                self.fetcher.step = match std::mem::replace(&mut self.fetcher.discard_fetch, false) {
+                    true => FetchStep::TileNumber,
+                    false => FetchStep::Push
+                };
+            },
 
-                    }
+            FetchStep::Push => if self.bg_fifo.is_empty() {
+                let attributes = self.fetcher.attributes;
+                for &colour in tile_row(self.fetcher.low, self.fetcher.high, attributes.flip_x).iter() {
+                    self.bg_fifo.push_back(Pixel { colour, palette: attributes.palette, priority: attributes.priority, index: 0 });
                 }
+
+                self.fetcher.tile_x = self.fetcher.tile_x.wrapping_add(1);
+                self.fetcher.step = FetchStep::TileNumber;
             }
         }
-
-        // if dump.len() > 0 {
-        //    println!("{:?}", dump);
-        //}
-
-        //self.display.draw(dump);
-
-        // 3 - H-Blank
     }
 
-
-    fn object_pixels(&mut self, visible: Vec<OAMEntry>) -> Vec<(Point, Color)> {
-
-        let mut pixels = Vec::new();
-
-        return pixels;
-
-        if visible.len() > 0 {
-            println!("******************************** active sprites");
+    /// The line of the background or window the fetcher is on
+    fn fetcher_y(&self) -> u8 {
+        match self.fetcher.window {
+            true => self.window_line,
+            false => self.ly.wrapping_add(self.scy)
         }
+    }
 
-        for object in visible.iter() {
-
-            let destination_x = ((object.x as i8) - 8) as i32;
-            let destination_y = ((object.y as i8) - 16) as i32;
+    /// Look up the number (and on a CGB, attributes) of the next tile in the tile map
+    fn fetch_tile_number(&mut self) {
 
-            let obj_y = match object.flags.flip_y {
-                true  => self.lcdc.obj_size().1 - 1 - (self.ly - object.y),
-                false => self.ly - object.y
-            };
+        let (map, column) = match self.fetcher.window {
+            true => (self.lcdc.window_tile_map_display_select().0, self.fetcher.tile_x),
+            false => (self.lcdc.bg_tile_map_display_select().0, (self.scx / 8).wrapping_add(self.fetcher.tile_x))
+        };
 
-            for x in 0..8 {
+        // The maps are 32 x 32 tiles, which the background wraps around
+        let map_address = map + (self.fetcher_y() as u16 / 8) * 32 + (column & 0x1F) as u16;
 
-                if (object.x as i8) + (x as i8) < 0 || object.x + x >= WIDTH as u8 {
-                    continue;
-                }
+        self.fetcher.tile_number = self.vram_byte(0, map_address);
+        self.fetcher.attributes = self.tile_attributes(map_address);
+    }
 
-                let col = match object.flags.flip_x {
-                    true => 7 - x,
-                    false => x
-                };
+    /// Read one of the bitplanes (0 low, 1 high) of the row of the fetcher's tile on this line
+    fn fetch_tile_data(&self, plane: u16) -> u8 {
 
-                let address = 0x8000 as u16 + (object.tile_number * 16) as u16 + (obj_y * 2) as u16;
+        let attributes = self.fetcher.attributes;
+        let row = match attributes.flip_y {
+            true => 7 - self.fetcher_y() % 8,
+            false => self.fetcher_y() % 8
+        } as u16;
 
-                let object_bytes = (self.read(address), self.read(address + 1));
+        let base = self.lcdc.tile_data_address(self.fetcher.tile_number);
+        self.vram_byte(attributes.bank, base + row * 2 + plane)
+    }
 
-                let color = (object_bytes.0 & (1 << col)) << 1 | (object_bytes.1 & (1 << col));
+    /// Whether the window starts at the next pixel: at WX - 7, or the left edge for WX under 7;
+    ///  WX 166 shows just the last pixel's worth, and beyond that the window is off the screen
+    fn window_starts(&self) -> bool {
+        self.lcdc.window_display_enable()
+            && self.window_y_triggered
+            && self.lx == self.wx.max(7) - 7
+    }
 
-                // Translucent
-                if color == 0b00 {
-                    continue;
-                }
+    /// Which of the line's objects still to be fetched, if any, starts at the next pixel; of
+    ///  several, the one furthest left, then the first in OAM
+    fn next_object(&self) -> Option<usize> {
+        match self.lcdc.obj_display_enable() {
+            true => self.line_objects.iter()
+                .enumerate()
+                .filter(|(_, object)| object.x <= self.lx + 8)
+                .min_by_key(|(_, object)| object.x)
+                .map(|(position, _)| position),
+            false => None
+        }
+    }
 
-                // TODO - Connect to "real" palette
-                let color = match color {
+    /// Fetch an object's row on this line, and merge it into the object FIFO
+    fn merge_object(&mut self, object: OAMEntry) {
+
+        let height = self.lcdc.obj_size().1;
+        let row = match object.flags.flip_y {
+            true => height - 1 - (self.ly + 16 - object.y) % height,
+            false => (self.ly + 16 - object.y) % height
+        } as u16;
+
+        // 8x16 objects ignore bit 0 of their tile number: the top tile is even, the bottom odd
+        let tile = match height {
+            16 => object.tile_number & 0xFE,
+            _ => object.tile_number
+        } as u16;
+        let address = 0x8000 + tile * 16 + row * 2;
+
+        let (bank, palette) = match self.cgb_mode {
+            true => (object.flags.bank, object.flags.cgb_palette),
+            false => (0, object.flags.palette as u8)
+        };
+        let pixels = tile_row(self.vram_byte(bank, address), self.vram_byte(bank, address + 1), object.flags.flip_x);
 
-                    0b01 => Color::RGB(80, 80, 80),
-                    0b10 => Color::RGB(140, 140, 140),
-                    0b11 => Color::RGB(200, 200, 200),
+        // An object partly off the left of the screen loses the pixels that are off it
+        let skip = (self.lx + 8).saturating_sub(object.x) as usize;
 
-                    _ => panic!("impossible color: {}", color)
-                };
+        for (slot, &colour) in pixels.iter().skip(skip).enumerate() {
+            let pixel = Pixel { colour, palette, priority: object.flags.priority, index: object.index };
 
-                pixels.push((Point::new(destination_x, destination_y), color));
+            // Where objects overlap, the one fetched first stays on top on a DMG, being further
+            //  left or earlier in OAM; on a CGB, the one earlier in OAM does
+            match self.obj_fifo.get_mut(slot) {
+                Some(existing) => if colour != 0 && (existing.colour == 0 || (self.cgb_mode && pixel.index < existing.index)) {
+                    *existing = pixel;
+                },
+                None => self.obj_fifo.push_back(pixel)
             }
         }
-
-        pixels
     }
 
-    fn background_pixels(&mut self) -> Vec<(Point, Color)> {
+    /// Shift a pixel out of the FIFOs onto the LCD, finishing the line after the 160th
+    fn shift_pixel(&mut self) {
 
-        let mut pixels: Vec<(Point, Color)> = Vec::new();
+        let background = self.bg_fifo.pop_front().unwrap_or_default();
+        let object = self.obj_fifo.pop_front().unwrap_or_default();
 
-        if !self.lcdc.bg_display() {
-            return pixels
-        }
+        let colour = self.pixel_colour(background, object);
+        self.display.set(Point::new(self.lx as i32, self.ly as i32), colour);
 
-        let base_address = self.lcdc.bg_tile_map_display_select().0;
-        let y_adjustment = ((self.scy / 8) * 32) as u16;
-
-        for i in 0..WIDTH {
-
-            let x = (((self.scx as usize) + i) % WIDTH) as u16;
-            let tile_base = base_address + y_adjustment + (x / 8);
+        self.lx += 1;
+        if self.lx as usize == WIDTH {
+            self.enter_mode(Mode0);
+        }
+    }
 
-            let address = tile_base + (self.scy % 8) as u16;
+    /// The colour on the LCD where a background / window pixel and an object pixel meet
+    fn pixel_colour(&self, mut background: Pixel, object: Pixel) -> Color {
 
-            println!("{:#06X}", self.lcdc.bg_tile_map_display_select().0);
+        // Outside of CGB mode, LCDC bit 0 blanks the background and window
+        if !self.cgb_mode && !self.lcdc.bg_display() {
+            background.colour = 0;
+        }
 
-            let lo_byte = self.read(address);
-            let hi_byte = self.read(address + 1);
+        // An object is behind background colours 1-3 if it asks to be, or on a CGB, if the tile
+        //  does; unless LCDC bit 0 takes away the background's priority on a CGB
+        let behind = match self.cgb_mode {
+            true => self.lcdc.bg_display() && (object.priority || background.priority),
+            false => object.priority
+        };
 
-            println!("{:#10b}, {:#10b}", hi_byte, lo_byte);
+        let object_shown = object.colour != 0
+            && self.lcdc.obj_display_enable()
+            && (!behind || background.colour == 0);
 
-            for j in (x % 8)..8 {
+        match object_shown {
+            true => self.object_colour(object),
+            false => self.background_colour(background)
+        }
+    }
 
-                let b1 = if hi_byte & (1 << (7 - j)) != 0 { 1 } else { 0 };
-                let b0 = if lo_byte & (1 << (7 - j)) != 0 { 1 } else { 0 };
+    /// The shade 0-3 that a DMG palette register (BGP, OBP0 or OBP1) gives a colour number
+    fn shade(palette: u8, colour: u8) -> u8 {
+        palette >> (colour * 2) & 0x03
+    }
 
-                let col = (b1 << 1) | b0;
+    /// The colour of a background / window pixel: through BGP on a DMG, and through the colour
+    ///  palettes on a CGB (in DMG compatibility mode, after BGP)
+    fn background_colour(&self, pixel: Pixel) -> Color {
+        match (self.cgb_mode, self.model.is_cgb()) {
+            (true, _) => self.bg_palettes.colour(pixel.palette, pixel.colour),
+            (false, true) => self.bg_palettes.colour(0, PPU::shade(self.bgp, pixel.colour)),
+            (false, false) => SHADES[PPU::shade(self.bgp, pixel.colour) as usize]
+        }
+    }
 
-                let color = match col {
-                    0b00 => Color::RGB(0, 0, 0),
-                    0b00 => Color::RGB(64, 64, 64),
-                    0b00 => Color::RGB(128, 128, 128),
-                    0b00 => Color::RGB(255, 255, 255),
+    /// The colour of an object pixel: through OBP0 / OBP1 on a DMG, and through the colour
+    ///  palettes on a CGB (in DMG compatibility mode, after OBP0 / OBP1)
+    fn object_colour(&self, pixel: Pixel) -> Color {
 
-                    _ => panic!("impossible color: {:#06b}", col)
-                };
+        let obp = match pixel.palette {
+            0 => self.obp0,
+            _ => self.obp1
+        };
 
-                pixels.push((Point::new(x as u32 as i32, self.ly as u32 as i32), color));
-            }
+        match (self.cgb_mode, self.model.is_cgb()) {
+            (true, _) => self.obj_palettes.colour(pixel.palette, pixel.colour),
+            (false, true) => self.obj_palettes.colour(pixel.palette, PPU::shade(obp, pixel.colour)),
+            (false, false) => SHADES[PPU::shade(obp, pixel.colour) as usize]
         }
-
-        pixels
     }
 
+    /*************************/
+    /*        Helpers        */
+    /*************************/
 
-    fn oam_entry(&mut self, entry_number: u8) -> OAMEntry {
+    fn oam_entry(&self, entry_number: u8) -> OAMEntry {
         assert!(entry_number < 40, "asking for entry number beyond 40");
 
-        // OAM entries are aligned on 4-byte boundaries beginning at 0xFE00
-        let oam_address: u16 = 0xFE00 + (entry_number * 4) as u16;
-        let flags = self.read(oam_address + 3);
+        // OAM entries are aligned on 4-byte boundaries
+        let offset = entry_number as usize * 4;
+        let flags = self.oam[offset + 3];
 
         OAMEntry {
-             y: self.read(oam_address),
-             x: self.read(oam_address + 1),
-            tile_number: self.read(oam_address + 2),
+             y: self.oam[offset],
+             x: self.oam[offset + 1],
+            tile_number: self.oam[offset + 2],
                   flags: OAMFlags {
                       priority: (flags & 0x80) >> 7 != 0,
                         flip_y: (flags & 0x40) >> 6 != 0,
                         flip_x: (flags & 0x20) >> 5 != 0,
-                       palette: (flags & 0x10) >> 4 != 0
-                  }
+                       palette: (flags & 0x10) >> 4 != 0,
+                          bank: ((flags & 0x08) >> 3) as usize,
+                   cgb_palette: flags & 0x07
+                  },
+            index: entry_number
+        }
+    }
+
+    /// Whether the CPU can reach palette RAM; it can't while the PPU is drawing from it
+    fn palettes_accessible(&self) -> bool {
+        self.lcds.mode_flag() != Mode3
+    }
+
+    /// The attributes of the background / window tile whose number is at a tile map address;
+    ///  all clear outside of CGB mode, where there are none
+    fn tile_attributes(&self, map_address: u16) -> TileAttributes {
+        match self.cgb_mode {
+            true => TileAttributes::from_byte(self.vram_byte(1, map_address)),
+            false => TileAttributes::default()
         }
     }
 
-    // Helper
+    /// A byte from a given bank of VRAM, as the PPU itself sees it
+    fn vram_byte(&self, bank: usize, address: u16) -> u8 {
+        self.vram[bank][PPU::addr_into_vram_space(address)]
+    }
 
     fn addr_into_vram_space(address: u16) -> usize {
         address as usize - 0x8000
@@ -463,44 +683,29 @@ impl PPU {
         address as usize - 0xFE00
     }
 
-    /// Returns the bg palette color in 00 ..= 11 associated with given 00 ..= 11
-    fn bg_palette(&mut self, color: u8) -> u8 {
-
-        let ff47 = self.read(0xFF47);
-
-        match color {
-            0b00 => ((ff47 & 0x01) << 1) | ((ff47 & 0x02) >> 1),    // Bits 0-1 -> 00
-            0b01 => ((ff47 & 0x04) >> 1) | ((ff47 & 0x08) >> 3),    // Bits 2-3 -> 01
-            0b10 => ((ff47 & 0x10) >> 3) | ((ff47 & 0x20) >> 5),    // Bits 4-5 -> 10
-            0b11 => ((ff47 & 0x40) >> 5) | ((ff47 & 0x80) >> 7),    // Bits 6-7 -> 11
-
-            _ => panic!("unexpected color code: {}", color)
-        }
-    }
-
     /*
        Potentially unused code. May be useful for the
        graphical implementations we create later.
      */
     #[allow(dead_code)]
     pub fn get_tile_set_1(&self) -> Vec<u8> {
-        self.vram[0..0x07ff].to_vec()
+        self.vram[0][0..0x0800].to_vec()
     }
     #[allow(dead_code)]
     pub fn get_tile_set_1_and_0(&self) -> Vec<u8> {
-        self.vram[0x0800..0x0FFF].to_vec()
+        self.vram[0][0x0800..0x1000].to_vec()
     }
     #[allow(dead_code)]
     pub fn get_tile_set_0(&self) -> Vec<u8> {
-        self.vram[0x1000..0x17ff].to_vec()
+        self.vram[0][0x1000..0x1800].to_vec()
     }
     #[allow(dead_code)]
     pub fn get_tile_map_0(&self) -> Vec<u8> {
-        self.vram[0x1800..0x1bff].to_vec()
+        self.vram[0][0x1800..0x1C00].to_vec()
     }
     #[allow(dead_code)]
     pub fn get_tile_map_1(&self) -> Vec<u8> {
-        self.vram[0x1c00..0x1FFF].to_vec()
+        self.vram[0][0x1C00..0x2000].to_vec()
     }
 }
 
@@ -513,7 +718,7 @@ impl MemoryMap for PPU {
             0x8000 ..= 0x9FFF => match self.lcds.mode_flag()  {
 
                 // Mode 0 / 1 / 2 allow VRAM access
-                Mode0 | Mode1 | Mode2 => self.vram[PPU::addr_into_vram_space(address)],
+                Mode0 | Mode1 | Mode2 => self.vram[self.vram_bank][PPU::addr_into_vram_space(address)],
 
                 // Cannot access VRAM / OAM in Mode 3
                 Mode3 => 0xFF,
@@ -535,47 +740,38 @@ impl MemoryMap for PPU {
             0xFF43 => self.scx,
             0xFF44 => self.ly,
             0xFF45 => self.lyc,
-            0xFF46 => self.dma,
             0xFF47 => self.bgp,
             0xFF48 => self.obp0,
             0xFF49 => self.obp1,
             0xFF4A => self.wy,
             0xFF4B => self.wx,
-            0xFF4D => 0x00,     // CGB Mode Only - KEY1 - Prepare Speed Switch
-            0xFF4F => 0x00,     // CGB Mode Only - VBK - VRAM Bank
 
-            // LCD VRAM DMA Transfers (CGB Mode Only)
-            0xFF51 => 0x00,     // HDMA1 - New DMA Source, High
-            0xFF52 => 0x00,     // HDMA2 - New DMA Source, Low
-            0xFF53 => 0x00,     // HDMA3 - New DMA Destination, High
-            0xFF54 => 0x00,     // HDMA4 - New DMA Destination, Low
-            0xFF55 => 0x00,     // HDMA5 - New DMA Length/Mode/Sort
+            // The CGB's registers don't exist on earlier models
+            0xFF4F | 0xFF68 ..= 0xFF6B if !self.model.is_cgb() => 0xFF,
+
+            // VRAM banking and the colour palettes are locked in DMG compatibility mode
+            0xFF4F | 0xFF68 ..= 0xFF6B if !self.cgb_mode => 0xFF,
+
+            0xFF4F => 0xFE | self.vram_bank as u8,  // CGB Mode Only - VBK - VRAM Bank
 
             // LCD Color Palettes (CGB Mode Only)
-            0xFF68 => 0x00,     // BCPS/BGPI - Background Palette Index
-            0xFF69 => 0x00,     // BCPD/BGPD - Background Palette Data
-            0xFF6A => 0x00,     // OCPS/OBPI - Sprite Palette Index
-            0xFF6B => 0x00,     // OCPD/OBPD - Sprite Palette Data
+            0xFF68 => self.bg_palettes.read_index(),                // BCPS/BGPI - Background Palette Index
+            0xFF69 => self.bg_palettes.read_data(self.palettes_accessible()),   // BCPD/BGPD - Background Palette Data
+            0xFF6A => self.obj_palettes.read_index(),               // OCPS/OBPI - Sprite Palette Index
+            0xFF6B => self.obj_palettes.read_data(self.palettes_accessible()),  // OCPD/OBPD - Sprite Palette Data
 
-            _ => panic!("unmapped address: {:#06X}", address)
+            _ => 0xFF
         }    }
 
     fn write(&mut self, address: u16, value: u8) {
 
-        // println!("address: {:#06X}", address);
-
-        if address == 0xFF00 || address == 0xFF01 {
-            println!("address: {:#06X} {:010b}", address, value);
-        }
-
-        // TODO - Add checks on VRAM / OAM against MODE to ensure access is possible
         match address {
 
             // VRAM Space
             0x8000 ..= 0x9FFF => match self.lcds.mode_flag()  {
 
                 // Mode 0 / 1 / 2 allow VRAM access
-                Mode0 | Mode1 | Mode2 => self.vram[PPU::addr_into_vram_space(address)] = value,
+                Mode0 | Mode1 | Mode2 => self.vram[self.vram_bank][PPU::addr_into_vram_space(address)] = value,
 
                 // Cannot access VRAM / OAM in Mode 3
                 Mode3 => (),
@@ -592,13 +788,12 @@ impl MemoryMap for PPU {
             },
 
             // I/O Registers
-            0xFF40 => self.lcdc.write(value),
+            0xFF40 => self.write_lcdc(value),
             0xFF41 => self.lcds.write(value),
             0xFF42 => self.scy = value,
             0xFF43 => self.scx = value,
             0xFF44 => (),   // read-only
             0xFF45 => self.lyc = value,
-            0xFF46 => self.dma_transfer(value),
             0xFF47 => self.bgp = value,
             0xFF48 => self.obp0 = value,
             0xFF49 => self.obp1 = value,
@@ -606,24 +801,22 @@ impl MemoryMap for PPU {
             0xFF4B => self.wx = value,
 
             0xFF4C => (),       // unmapped
-            0xFF4D => (),       // CGB Mode Only - KEY1 - Prepare Speed Switch
             0xFF4E => (),       // unmapped
+            0xFF4F if self.cgb_mode => self.vram_bank = (value & 0x01) as usize,
             0xFF4F => (),       // CGB Mode Only - VBK - VRAM Bank
 
-            // LCD VRAM DMA Transfers (CGB Mode Only)
-            0xFF51 => (),       // HDMA1 - New DMA Source, High
-            0xFF52 => (),       // HDMA2 - New DMA Source, Low
-            0xFF53 => (),       // HDMA3 - New DMA Destination, High
-            0xFF54 => (),       // HDMA4 - New DMA Destination, Low
-            0xFF55 => (),       // HDMA5 - New DMA Length/Mode/Sort
-
             // LCD Color Palettes (CGB Mode Only)
+            0xFF68 if self.cgb_mode => self.bg_palettes.write_index(value),
+            0xFF69 if self.cgb_mode => self.bg_palettes.write_data(value, self.palettes_accessible()),
+            0xFF6A if self.cgb_mode => self.obj_palettes.write_index(value),
+            0xFF6B if self.cgb_mode => self.obj_palettes.write_data(value, self.palettes_accessible()),
+
             0xFF68 => (),       // BCPS/BGPI - Background Palette Index
             0xFF69 => (),       // BCPD/BGPD - Background Palette Data
             0xFF6A => (),       // OCPS/OBPI - Sprite Palette Index
             0xFF6B => (),       // OCPD/OBPD - Sprite Palette Data
 
-            _ => panic!("unmapped address: {:#06X}", address)
+            _ => ()
         }    }
 }
 
@@ -631,20 +824,420 @@ impl MemoryMap for PPU {
 #[cfg(test)]
 mod test {
 
-    #[allow(unused_imports)]
-    use crate::testing::mooneye_all;
+    use crate::enums::Mode;
+    use crate::model::Model;
+    use crate::ppu::{PPU, WIDTH};
+    use crate::testing::{dmg_acid2, mooneye_all};
+    use crate::traits::MemoryMap;
+
+    /// A DMG PPU with tile 1 solid colour 3, the first map row full of it, and BGP showing each
+    ///  colour as its own shade
+    fn ppu() -> PPU {
+        let mut ppu = PPU::new(Model::DMG);
+        for address in 0x8010..0x8020 {
+            ppu.write(address, 0xFF);
+        }
+        for address in 0x9800..0x9820 {
+            ppu.write(address, 0x01);
+        }
+        ppu.write(0xFF47, 0xE4);
+        ppu
+    }
+
+    fn run_dots(ppu: &mut PPU, dots: u64) {
+        ppu.run_for(dots * 4);
+    }
+
+    /// Turn the LCD on, and count the dots of the second line's pixel transfer; the first has no
+    ///  OAM scan, so it never has objects
+    fn mode_3_length(ppu: &mut PPU, lcdc: u8) -> u16 {
+        ppu.write(0xFF40, lcdc);
+        run_dots(ppu, 456 + 80);
+        assert!(ppu.mode == Mode::Mode3);
+
+        let mut dots = 0;
+        while ppu.mode == Mode::Mode3 {
+            run_dots(ppu, 1);
+            dots += 1;
+        }
+        dots
+    }
+
+    /// The shade of a pixel of the current frame, by its red component
+    fn pixel(ppu: &PPU, x: usize, y: usize) -> u8 {
+        ppu.framebuffer()[(y * WIDTH + x) * 3]
+    }
 
-    /* TODO
     #[test]
-    fn acceptance_oam_dma() {
-        mooneye_all(&format!("{}/{}", MOONEYE, "acceptance/oam_dma"));
+    fn mode_3_lengthens() {
+        assert_eq!(mode_3_length(&mut ppu(), 0x91), 172);
+
+        let mut scrolled = ppu();
+        scrolled.write(0xFF43, 0x03);
+        assert_eq!(mode_3_length(&mut scrolled, 0x91), 175);
+
+        let mut window = ppu();
+        window.write(0xFF4B, 0x57);
+        assert_eq!(mode_3_length(&mut window, 0xB1), 178);
+
+        // An object on the line costs 6 dots, and up to 6 more waiting on the fetcher, but only
+        //  while objects are on
+        let mut object = ppu();
+        object.write(0xFE00, 0x10);
+        object.write(0xFE01, 0x20);
+        let mut hidden = ppu();
+        hidden.write(0xFE00, 0x10);
+        hidden.write(0xFE01, 0x20);
+        assert_eq!(mode_3_length(&mut hidden, 0x91), 172);
+        let length = mode_3_length(&mut object, 0x93);
+        assert!((178..=184).contains(&length), "{}", length);
+    }
+
+    #[test]
+    fn stat_modes_and_interrupts() {
+        let mut ppu = ppu();
+        ppu.write(0xFF41, 0x08);
+        ppu.write(0xFF40, 0x91);
+
+        // The first line after the LCD is turned on waits in mode 0, without an HBlank interrupt
+        assert_eq!(ppu.read(0xFF41), 0x8C);
+        run_dots(&mut ppu, 79);
+        assert_eq!(ppu.read(0xFF41), 0x8C);
+        assert!(!ppu.stat_interrupt);
+
+        run_dots(&mut ppu, 1);
+        assert_eq!(ppu.read(0xFF41), 0x8F);
+        assert!(!ppu.stat_interrupt);
+
+        // HBlank raises the STAT line, then the line moves on from LYC
+        run_dots(&mut ppu, 172);
+        assert_eq!(ppu.read(0xFF41), 0x8C);
+        assert!(ppu.stat_interrupt);
+
+        run_dots(&mut ppu, 456 - 252);
+        assert_eq!(ppu.read(0xFF44), 0x01);
+        assert_eq!(ppu.read(0xFF41), 0x8A);
+
+        // VRAM is locked during pixel transfer
+        assert_eq!(ppu.read(0x9800), 0x01);
+        run_dots(&mut ppu, 80);
+        assert_eq!(ppu.read(0x9800), 0xFF);
+        ppu.write(0x8000, 0x12);
+        run_dots(&mut ppu, 456 - 80);
+        assert_eq!(ppu.read(0x8000), 0x00);
+
+        // Turning the LCD off resets LY
+        ppu.write(0xFF40, 0x11);
+        assert_eq!(ppu.read(0xFF44), 0x00);
+        assert_eq!(ppu.read(0xFF41), 0x88);
+    }
+
+    #[test]
+    fn first_line_skips_oam_scan() {
+        let mut ppu = ppu();
+        ppu.write(0xFF48, 0xE4);
+        for address in 0x9800..0x9820 {
+            ppu.write(address, 0x00);
+        }
+        object(&mut ppu, 0, 16, 8, 0x01, 0x00);
+
+        // OAM stays open to the CPU, and the object is only found from the second line
+        ppu.write(0xFF40, 0x93);
+        assert_eq!(ppu.read(0xFE00), 16);
+        run_dots(&mut ppu, 456 * 2);
+
+        assert_eq!(pixel(&ppu, 0, 0), 0xFF);
+        assert_eq!(pixel(&ppu, 0, 1), 0x00);
+    }
+
+    #[test]
+    fn vblank_raises_mode_2_interrupt() {
+        let mut ppu = ppu();
+        ppu.write(0xFF41, 0x20);
+        ppu.write(0xFF40, 0x91);
+
+        run_dots(&mut ppu, 456 * 143 + 80);
+        ppu.stat_interrupt = false;
+
+        // Line 144 starts VBlank with the mode 2 interrupt, though there's no OAM scan
+        run_dots(&mut ppu, 456 - 80);
+        assert_eq!(ppu.read(0xFF44), 144);
+        assert_eq!(ppu.read(0xFF41) & 0x03, 0x01);
+        assert!(ppu.stat_interrupt);
+
+        // But not on the lines after it
+        ppu.stat_interrupt = false;
+        run_dots(&mut ppu, 456 * 9);
+        assert_eq!(ppu.read(0xFF44), 153);
+        assert!(!ppu.stat_interrupt);
+    }
+
+    #[test]
+    fn mid_line_writes() {
+        let mut ppu = ppu();
+        ppu.write(0xFF40, 0x91);
+
+        // The background is turned off part way through the first line, blanking the rest
+        run_dots(&mut ppu, 80 + 12 + 80);
+        ppu.write(0xFF40, 0x90);
+        run_dots(&mut ppu, 456 - 172);
+
+        assert_eq!(pixel(&ppu, 0, 0), 0x00);
+        assert_eq!(pixel(&ppu, 79, 0), 0x00);
+        assert_eq!(pixel(&ppu, 80, 0), 0xFF);
+        assert_eq!(pixel(&ppu, 159, 0), 0xFF);
+
+        // Back on for the second line, with tile 0 under it from the ninth
+        ppu.write(0xFF40, 0x91);
+        run_dots(&mut ppu, 456);
+        assert_eq!(pixel(&ppu, 0, 1), 0x00);
+        assert_eq!(pixel(&ppu, 0, 8), 0xFF);
+    }
+
+    #[test]
+    fn background_tile_addressing() {
+
+        // Tiles of colour 1 at 0x8800, colour 2 at 0x9000 and colour 3 at 0x97F0
+        let mut ppu = ppu();
+        for (base, low, high) in [(0x8800, 0xFF, 0x00), (0x9000, 0x00, 0xFF), (0x97F0, 0xFF, 0xFF)].iter() {
+            for row in 0..8 {
+                ppu.write(base + row * 2, *low);
+                ppu.write(base + row * 2 + 1, *high);
+            }
+        }
+        for (column, &tile) in [0x80, 0x00, 0x7F, 0x01].iter().enumerate() {
+            ppu.write(0x9800 + column as u16, tile);
+        }
+
+        // 0x8800 addressing: signed tile numbers around 0x9000
+        ppu.write(0xFF40, 0x81);
+        run_dots(&mut ppu, 456);
+        assert_eq!(pixel(&ppu, 0, 0), 0xAD);
+        assert_eq!(pixel(&ppu, 8, 0), 0x52);
+        assert_eq!(pixel(&ppu, 16, 0), 0x00);
+        assert_eq!(pixel(&ppu, 24, 0), 0xFF);
+
+        // 0x8000 addressing, with BGP reversing the shades
+        ppu.write(0xFF40, 0x00);
+        ppu.write(0xFF47, 0x1B);
+        ppu.write(0xFF40, 0x91);
+        run_dots(&mut ppu, 456);
+        assert_eq!(pixel(&ppu, 0, 0), 0x52);
+        assert_eq!(pixel(&ppu, 8, 0), 0x00);
+        assert_eq!(pixel(&ppu, 16, 0), 0x00);
+        assert_eq!(pixel(&ppu, 24, 0), 0xFF);
+    }
+
+    #[test]
+    fn background_wraps() {
+
+        // Tile 3 is solid colour 1; the map's last column has tile 1 at the top, tile 3 at the bottom
+        let mut ppu = ppu();
+        for address in 0x8030..0x8040 {
+            ppu.write(address, (address & 0x01 == 0) as u8 * 0xFF);
+        }
+        for address in 0x9800..0x9C00 {
+            ppu.write(address, 0x00);
+        }
+        ppu.write(0x981F, 0x01);
+        ppu.write(0x9BFF, 0x03);
+
+        // Scrolled so the top left corner of the screen shows the map's bottom right corner
+        ppu.write(0xFF42, 0xFC);
+        ppu.write(0xFF43, 0xFC);
+        ppu.write(0xFF40, 0x91);
+        run_dots(&mut ppu, 456 * 5);
+
+        assert_eq!(pixel(&ppu, 3, 3), 0xAD);
+        assert_eq!(pixel(&ppu, 4, 3), 0xFF);
+        assert_eq!(pixel(&ppu, 3, 4), 0x00);
+        assert_eq!(pixel(&ppu, 4, 4), 0xFF);
+        assert_eq!(pixel(&ppu, 159, 4), 0xFF);
+    }
+
+    /// A PPU with the background all white, and a window map at 0x9C00 whose first row is a
+    ///  given tile; tile 2 is black on the left half, white on the right
+    fn window_ppu(tile: u8, wx: u8) -> PPU {
+        let mut ppu = ppu();
+        for address in 0x8020..0x8030 {
+            ppu.write(address, 0xF0);
+        }
+        for address in 0x9800..0x9820 {
+            ppu.write(address, 0x00);
+        }
+        for address in 0x9C00..0x9C20 {
+            ppu.write(address, tile);
+        }
+        ppu.write(0xFF4B, wx);
+        ppu
+    }
+
+    #[test]
+    fn window_line_counter() {
+        let mut ppu = window_ppu(0x01, 0x07);
+
+        // The window is on for 4 lines, off for 8, then back on
+        ppu.write(0xFF40, 0xF1);
+        run_dots(&mut ppu, 456 * 4);
+        ppu.write(0xFF40, 0xD1);
+        run_dots(&mut ppu, 456 * 8);
+        ppu.write(0xFF40, 0xF1);
+        run_dots(&mut ppu, 456 * 5);
+
+        assert_eq!(pixel(&ppu, 0, 3), 0x00);
+        assert_eq!(pixel(&ppu, 0, 4), 0xFF);
+        assert_eq!(pixel(&ppu, 159, 11), 0xFF);
+
+        // Carrying on from its fifth line, rather than LY's
+        assert_eq!(pixel(&ppu, 0, 12), 0x00);
+        assert_eq!(pixel(&ppu, 159, 15), 0x00);
+        assert_eq!(pixel(&ppu, 0, 16), 0xFF);
+    }
+
+    #[test]
+    fn window_waits_for_wy() {
+        let mut ppu = window_ppu(0x01, 0x07);
+
+        // WY is passed over by LY before it's set, so the window waits for the next frame
+        ppu.write(0xFF4A, 0x90);
+        ppu.write(0xFF40, 0xF1);
+        run_dots(&mut ppu, 456 * 2);
+        ppu.write(0xFF4A, 0x01);
+        run_dots(&mut ppu, 456 * 2);
+        assert_eq!(pixel(&ppu, 0, 1), 0xFF);
+        assert_eq!(pixel(&ppu, 0, 3), 0xFF);
+
+        // Then matches on line 1, and stays on however WY changes after
+        run_dots(&mut ppu, 456 * 152);
+        ppu.write(0xFF4A, 0x50);
+        run_dots(&mut ppu, 456);
+        assert_eq!(pixel(&ppu, 0, 0), 0xFF);
+        assert_eq!(pixel(&ppu, 0, 1), 0x00);
+        assert_eq!(pixel(&ppu, 0, 2), 0x00);
+    }
+
+    #[test]
+    fn window_edges() {
+
+        // WX under 7 loses the window's leftmost pixels off the screen
+        let mut ppu = window_ppu(0x02, 0x03);
+        ppu.write(0xFF40, 0xF1);
+        run_dots(&mut ppu, 456);
+        assert_eq!(pixel(&ppu, 0, 0), 0xFF);
+        assert_eq!(pixel(&ppu, 3, 0), 0xFF);
+        assert_eq!(pixel(&ppu, 4, 0), 0x00);
+        assert_eq!(pixel(&ppu, 8, 0), 0xFF);
+
+        // WX 166 covers only the last pixel
+        let mut ppu = window_ppu(0x01, 0xA6);
+        ppu.write(0xFF40, 0xF1);
+        run_dots(&mut ppu, 456);
+        assert_eq!(pixel(&ppu, 158, 0), 0xFF);
+        assert_eq!(pixel(&ppu, 159, 0), 0x00);
+
+        // Turning the window off part way through a line brings the background back
+        let mut ppu = window_ppu(0x01, 0x07);
+        ppu.write(0xFF40, 0xF1);
+        run_dots(&mut ppu, 80 + 12 + 80);
+        ppu.write(0xFF40, 0xD1);
+        run_dots(&mut ppu, 456 - 172);
+        assert_eq!(pixel(&ppu, 60, 0), 0x00);
+        assert_eq!(pixel(&ppu, 120, 0), 0xFF);
+    }
+
+    /// Write an object's OAM entry
+    fn object(ppu: &mut PPU, index: u16, y: u8, x: u8, tile: u8, flags: u8) {
+        let address = 0xFE00 + index * 4;
+        for (offset, &value) in [y, x, tile, flags].iter().enumerate() {
+            ppu.write(address + offset as u16, value);
+        }
+    }
+
+    #[test]
+    fn object_priority() {
+        let mut ppu = ppu();
+        ppu.write(0xFF48, 0xE4);
+        ppu.write(0xFF49, 0x90);
+
+        // Overlapping: further left wins over earlier in OAM, then earlier in OAM wins
+        object(&mut ppu, 0, 16, 20, 0x01, 0x00);
+        object(&mut ppu, 1, 16, 16, 0x01, 0x10);
+        object(&mut ppu, 2, 16, 40, 0x01, 0x10);
+        object(&mut ppu, 3, 16, 40, 0x01, 0x00);
+
+        // Behind the background's colour 3 on line 4, but over its colour 0 from line 8
+        object(&mut ppu, 4, 20, 68, 0x01, 0x90);
+
+        ppu.write(0xFF40, 0x93);
+        run_dots(&mut ppu, 456 * 9);
+
+        assert_eq!(pixel(&ppu, 8, 1), 0x52);
+        assert_eq!(pixel(&ppu, 15, 1), 0x52);
+        assert_eq!(pixel(&ppu, 16, 1), 0x00);
+        assert_eq!(pixel(&ppu, 32, 1), 0x52);
+        assert_eq!(pixel(&ppu, 60, 4), 0x00);
+        assert_eq!(pixel(&ppu, 60, 8), 0x52);
+    }
+
+    #[test]
+    fn object_flips_and_tall_objects() {
+        let mut ppu = ppu();
+        ppu.write(0xFF48, 0xE4);
+        for address in 0x9800..0x9820 {
+            ppu.write(address, 0x00);
+        }
+
+        // Tile 4's top-left pixel and tile 5's bottom-right, drawn as one 8x16 object from
+        //  either tile number
+        ppu.write(0x8040, 0x80);
+        ppu.write(0x8041, 0x80);
+        ppu.write(0x805E, 0x01);
+        ppu.write(0x805F, 0x01);
+
+        object(&mut ppu, 0, 17, 8, 0x05, 0x00);
+        object(&mut ppu, 1, 17, 24, 0x04, 0x60);
+
+        ppu.write(0xFF40, 0x97);
+        run_dots(&mut ppu, 456 * 17);
+
+        assert_eq!(pixel(&ppu, 0, 1), 0x00);
+        assert_eq!(pixel(&ppu, 7, 1), 0xFF);
+        assert_eq!(pixel(&ppu, 7, 16), 0x00);
+
+        assert_eq!(pixel(&ppu, 16, 1), 0x00);
+        assert_eq!(pixel(&ppu, 23, 1), 0xFF);
+        assert_eq!(pixel(&ppu, 23, 16), 0x00);
+        assert_eq!(pixel(&ppu, 16, 16), 0xFF);
+    }
+
+    #[test]
+    fn ten_objects_per_line() {
+        let mut ppu = ppu();
+        ppu.write(0xFF48, 0xE4);
+        for address in 0x9800..0x9820 {
+            ppu.write(address, 0x00);
+        }
+
+        // Eleven objects right to left: the last in OAM is the one left off, not the rightmost
+        for index in 0..11 {
+            object(&mut ppu, index, 16, 160 - index as u8 * 8, 0x01, 0x00);
+        }
+
+        ppu.write(0xFF40, 0x93);
+        run_dots(&mut ppu, 456 * 2);
+
+        assert_eq!(pixel(&ppu, 159, 1), 0x00);
+        assert_eq!(pixel(&ppu, 80, 1), 0x00);
+        assert_eq!(pixel(&ppu, 79, 1), 0xFF);
     }
-     */
 
-    /* TODO
     #[test]
     fn acceptance_ppu() {
         mooneye_all("acceptance/ppu");
     }
-     */
+
+    #[test]
+    fn acceptance_dmg_acid2() {
+        dmg_acid2();
+    }
 }
\ No newline at end of file
@@ -1,15 +1,20 @@
 /// An entry in the OAM table for a sprite
+#[derive(Copy, Clone, Debug)]
 pub struct OAMEntry {
     pub y: u8,              // Position X
     pub x: u8,              // Position Y
     pub tile_number: u8,    // Tile Number
-    pub flags: OAMFlags     // Flags
+    pub flags: OAMFlags,    // Flags
+    pub index: u8           // Entry number 0 - 39, which decides priority between objects on a CGB
 }
 
 
+#[derive(Copy, Clone, Debug)]
 pub struct OAMFlags {
     pub priority: bool,   // OBJ-to-BG Priority   (0 = above BG,      1 = behind BG colors 1 - 3)
     pub   flip_y: bool,   // Y Flip               (0 = normal,        1 = vertical mirror)
     pub   flip_x: bool,   // X Flip               (0 = normal,        1 = horizontal mirror)
-    pub  palette: bool    // Object Palette       (0 = obp0 @ 0xFF48, 1 = obp1 @ 0xFF49)
+    pub  palette: bool,   // Object Palette       (0 = obp0 @ 0xFF48, 1 = obp1 @ 0xFF49)
+    pub     bank: usize,  // Tile VRAM Bank       (CGB only)
+    pub cgb_palette: u8   // Object Palette 0 - 7 (CGB only)
 }
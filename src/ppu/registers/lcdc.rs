@@ -91,6 +91,16 @@ impl LCDC {
     /// controls which addressing mode the BG and Window use to pick tiles
     pub fn bg_window_tile_data_select(&self) -> (u16, u16) { self.bg_window_tile_data_select }
 
+    /// Address of a BG / Window tile's data
+    /// 0x8000 addressing numbers tiles 0-255 up from 0x8000, while 0x8800 addressing treats the
+    /// tile number as signed, -128 to 127 around 0x9000
+    pub fn tile_data_address(&self, tile: u8) -> u16 {
+        match self.bg_window_tile_data_select == BG_WINDOW_TILE_DATA_SELECT_1 {
+            true => 0x8000 + tile as u16 * 16,
+            false => 0x9000u16.wrapping_add((tile as i8 as i16 * 16) as u16)
+        }
+    }
+
     /// BG Tile Map Area
     /// Similar to the window tile map area
     pub fn bg_tile_map_display_select(&self) -> (u16, u16) { self.bg_tile_map_display_select }
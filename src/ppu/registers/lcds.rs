@@ -31,7 +31,7 @@ pub struct LCDS {
     /// See Mode enum.
     mode_flag: Mode,
 
-    /// Real 8-bit value stored
+    /// Bits 3-6 as written; the rest are set by the PPU
     value: u8
 }
 
@@ -67,34 +67,38 @@ impl LCDS {
 
     /// Mode Flag
     pub fn mode_flag(&self) -> Mode { self.mode_flag }
+
+    /// Set the mode the PPU is in, for bits 0-1
+    pub fn set_mode(&mut self, mode: Mode) { self.mode_flag = mode }
+
+    /// Set whether LY matches LYC, for bit 2
+    pub fn set_coincidence(&mut self, coincidence: bool) { self.coincidence_flag = coincidence }
 }
 
 
 impl Byte for LCDS {
 
+    /// Bit 7 is unused, and always reads set
     fn read(&self) -> u8 {
-        self.value
+        let mode = match self.mode_flag {
+            Mode::Mode0 => 0b00,
+            Mode::Mode1 => 0b01,
+            Mode::Mode2 => 0b10,
+            Mode::Mode3 => 0b11
+        };
+
+        0x80 | self.value | (self.coincidence_flag as u8) << 2 | mode
     }
 
-    /// Write given value into LCDS Register, populating ease-of-access fields
+    /// Write given value into LCDS Register, populating ease-of-access fields; the coincidence
+    ///  flag and mode are read-only
     fn write(&mut self, value: u8) {
 
-        self.value = value;
+        self.value = value & 0x78;
 
         self.lyc_interrupt = value & 0x40 != 0;                 // Bit 6
         self.mode_2_oam_interrupt = value & 0x20 != 0;          // Bit 5
         self.mode_1_v_blank_interrupt = value & 0x10 != 0;      // Bit 4
         self.mode_0_h_blank_interrupt = value & 0x08 != 0;      // Bit 3
-        self.coincidence_flag = value & 0x04 != 0;              // Bit 2
-
-        // Bits 0-1
-        self.mode_flag = match value & 0x03 {
-            0b00 => Mode::Mode0,
-            0b01 => Mode::Mode1,
-            0b10 => Mode::Mode2,
-            0b11 => Mode::Mode3,
-
-            _ => panic!("arithmetic error: {}", value)
-        };
     }
-}
\ No newline at end of file
+}
@@ -50,7 +50,7 @@ impl MemoryMap for Serial {
         match address {
             0xFF01 => self.serial_transfer_data,
             0xFF02 => self.serial_transfer_control,
-            _ => panic!("serial link cable is not mapped to by address: {:#010X}", address)
+            _ => 0xFF
         }
     }
 
@@ -58,7 +58,7 @@ impl MemoryMap for Serial {
         match address {
             0xFF01 => self.serial_transfer_data = value,
             0xFF02 => self.serial_transfer_control = value,
-            _ => panic!("serial link cable is not mapped to by address: {:#010X}", address)
+            _ => ()
         }
     }
 }
@@ -1,8 +1,30 @@
+use crate::model::Model;
 use crate::traits::{MemoryMap, RunComponent};
 
+
+/// Rate (in Hz) of the interleaved stereo samples produced for a frontend to play
+pub const SAMPLE_RATE: u64 = 44100;
+
+/// Rate (in Hz) of the clock the APU is run with
+const CPU_CLOCK: u64 = 4_194_304;
+
+/// Wave RAM at power on: the CGB clears it to alternating 0x00 / 0xFF, while on earlier models it's
+///  whatever the RAM happens to hold; this is one pattern commonly read back from a DMG
+const DMG_WAVE_RAM: [u8; 16] = [
+    0x84, 0x40, 0x43, 0xAA, 0x2D, 0x78, 0x92, 0x3C, 0x60, 0x59, 0x59, 0xB0, 0x34, 0xB8, 0x2E, 0xDA
+];
+const CGB_WAVE_RAM: [u8; 16] = [
+    0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF, 0x00, 0xFF
+];
+
+/// Upper bound on buffered samples (one second of stereo) if nothing is taking them
+const MAX_BUFFERED_SAMPLES: usize = (SAMPLE_RATE * 2) as usize;
+
 #[allow(dead_code)]
 pub struct Sound {
 
+    model: Model,
+
     // Channel 1 : Tone & Sweep - 0xFF10-0xFF14
     channel_1_sweep: u8,                // 0xFF10
     channel_1_length_pattern: u8,       // 0xFF11
@@ -36,13 +58,19 @@ pub struct Sound {
     channel_control_on_off: u8,         // 0xFF24
     sound_output_terminal: u8,          // 0xFF25
     sound_on_off: u8,                   // 0xFF26
+
+    samples: Vec<i16>,                  // Interleaved stereo (L, R) samples not yet taken
+    sample_tank: u64,                   // "Tank" of CPU cycles (scaled by SAMPLE_RATE) to produce
+                                        // samples at the appropriate rate
 }
 
 
 impl Sound {
 
-    pub fn new() -> Sound {
+    pub fn new(model: Model) -> Sound {
         Sound {
+            model,
+
             channel_1_sweep: 0,
             channel_1_length_pattern: 0,
             channel_1_volume_envelope: 0,
@@ -59,7 +87,10 @@ impl Sound {
             channel_3_select_output_level: 0,
             channel_3_frequency_lo: 0,
             channel_3_frequency_hi: 0,
-            wave_pattern_data: [0; 16],
+            wave_pattern_data: match model.is_cgb() {
+                true => CGB_WAVE_RAM,
+                false => DMG_WAVE_RAM
+            },
 
             channel_4_sound_length: 0,
             channel_4_volume_envelope: 0,
@@ -68,9 +99,17 @@ impl Sound {
 
             channel_control_on_off: 0,
             sound_output_terminal: 0,
-            sound_on_off: 0
+            sound_on_off: 0,
+
+            samples: Vec::new(),
+            sample_tank: 0
         }
     }
+
+    /// Take all samples produced since the last call, as interleaved stereo (L, R) pairs
+    pub fn take_samples(&mut self) -> Vec<i16> {
+        std::mem::take(&mut self.samples)
+    }
 }
 
 
@@ -112,7 +151,7 @@ impl MemoryMap for Sound {
             // Wave Pattern RAM - 0xFF30 - FF3F
             0xFF30..=0xFF3F => self.wave_pattern_data[(address % 0xFF30) as usize],
 
-            _ => panic!("unmapped sound register: {:060X}", address)
+            _ => 0xFF
         }
     }
 
@@ -152,7 +191,7 @@ impl MemoryMap for Sound {
             // Wave Pattern RAM - 0xFF30 - FF3F
             0xFF30..=0xFF3F => self.wave_pattern_data[(address % 0xFF30) as usize] = value,
 
-            _ => panic!("unmapped sound register: {:060X}", address)
+            _ => ()
         };
     }
 }
@@ -160,7 +199,22 @@ impl MemoryMap for Sound {
 
 impl RunComponent for Sound {
 
-    fn run(&mut self, _cpu_clock_cycles: u64) {
-        // TODO
+    fn run(&mut self, cpu_clock_cycles: u64) {
+
+        self.sample_tank += cpu_clock_cycles * SAMPLE_RATE;
+
+        while self.sample_tank >= CPU_CLOCK {
+
+            // Nothing is consuming the samples; drop them rather than growing forever
+            if self.samples.len() >= MAX_BUFFERED_SAMPLES {
+                self.samples.clear();
+            }
+
+            // TODO - Mix the four channels; silence until they are implemented
+            self.samples.push(0);
+            self.samples.push(0);
+
+            self.sample_tank -= CPU_CLOCK;
+        }
     }
 }
\ No newline at end of file
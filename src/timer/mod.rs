@@ -29,8 +29,8 @@ impl Timer {
             timer_counter: 0,
             timer_modulo: 0,
             timer_control: 0,
-            timer_enabled: true,
-            timer_clock_select: 0,
+            timer_enabled: false,
+            timer_clock_select: 1024,
             divi_tank: 0,
             tima_tank: 0,
             interrupt: false,
@@ -48,7 +48,7 @@ impl MemoryMap for Timer {
             0xFF06 => self.timer_modulo,
             0xFF07 => self.timer_control,
 
-            _ => panic!("unmapped timer address: {:#06X}", address)
+            _ => 0xFF
         }    }
 
     fn write(&mut self, address: u16, value: u8) {
@@ -69,7 +69,7 @@ impl MemoryMap for Timer {
                 };
             },
 
-            _ => panic!("unmapped timer address: {:#06X}", address)
+            _ => ()
         }
     }
 }
@@ -1,3 +1,8 @@
+use crate::joypad::{Buttons, Tilt};
+use crate::mmu::mbc::camera::SensorImage;
+use crate::mmu::mbc::rtc::ClockSource;
+
+
 /// The GameBoy uses memory-mapped I/O; implementing a read / write trait ensures consistency
 /// across various modules that will utilize this concept, as well as clarify the meaning of
 /// a "read" or "write" in relation to a specific module.
@@ -21,9 +26,57 @@ pub trait Byte {
 pub trait MBC {
     fn read(&mut self, address: u16) -> u8;
     fn write(&mut self, address: u16, value: u8);
+
+    /// Advance anything on the cartridge that keeps running by itself, such as a real-time clock,
+    ///  by a number of clock cycles
+    fn run(&mut self, _clock_cycles: u64) {}
+
+    /// Choose what drives the cartridge's real-time clock, for cartridges that have one
+    fn set_clock_source(&mut self, _source: ClockSource) {}
+
+    /// Feed the cartridge's accelerometer, for cartridges that have one
+    fn set_tilt(&mut self, _tilt: Tilt) {}
+
+    /// Change what the cartridge's camera sees, for cartridges that have one
+    fn set_sensor_image(&mut self, _image: SensorImage) {}
+
+    /// Whether the cartridge's rumble motor has been running at any point since last asked
+    fn take_rumble(&mut self) -> bool { false }
+
+    /// The contents of battery-backed RAM, in the raw .sav layout other emulators share; None
+    ///  for cartridges without a battery, whose RAM is lost at power off anyway
+    fn save_data(&self) -> Option<Vec<u8>> { None }
+
+    /// Restore battery-backed RAM from a previous session's save_data
+    fn load_save_data(&mut self, _data: &[u8]) {}
 }
 
 
 pub trait RunComponent {
     fn run(&mut self, cpu_clock_cycles: u64);
 }
+
+
+/// Everything outside the GameBoy itself: where finished frames and audio go, and where button
+/// presses come from. The core calls into this once per frame, at VBlank, and never needs to know
+/// whether it is talking to a window, a test harness, or nothing at all.
+pub trait Frontend {
+
+    /// Show a completed frame, as RGB24 bytes in row-major order (160 x 144 pixels)
+    fn present_frame(&mut self, framebuffer: &[u8]);
+
+    /// Play audio samples, as interleaved stereo (L, R) pairs at `sound::SAMPLE_RATE`
+    fn push_audio_samples(&mut self, samples: &[i16]);
+
+    /// The buttons currently held down
+    fn poll_buttons(&mut self) -> Buttons;
+
+    /// How the GameBoy is tilted, for cartridges with an accelerometer; level by default
+    fn poll_tilt(&mut self) -> Tilt {
+        Tilt::default()
+    }
+
+    /// Whether a rumble cartridge's motor ran during the last frame, for frontends that can show
+    ///  or feel it; called once per frame
+    fn set_rumble(&mut self, _active: bool) {}
+}